@@ -1,10 +1,32 @@
 // Copyright 2022, Offchain Labs, Inc.
 // For license information, see https://github.com/nitro/blob/master/LICENSE
 
+use blake2::{digest::consts::U32, Blake2b};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
 use siphasher::sip::SipHasher24;
 use std::mem::MaybeUninit;
 use tiny_keccak::{Hasher, Keccak};
 
+/// The hash functions available for hashing guest memory, each producing a 32-byte digest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgo {
+    Keccak256,
+    Sha256,
+    Blake2b256,
+}
+
+impl HashAlgo {
+    pub fn hash<T: AsRef<[u8]>>(self, preimage: T) -> [u8; 32] {
+        let preimage = preimage.as_ref();
+        match self {
+            Self::Keccak256 => keccak(preimage),
+            Self::Sha256 => sha256(preimage),
+            Self::Blake2b256 => Blake2b::<U32>::digest(preimage).into(),
+        }
+    }
+}
+
 pub fn keccak<T: AsRef<[u8]>>(preimage: T) -> [u8; 32] {
     let mut output = MaybeUninit::<[u8; 32]>::uninit();
     let mut hasher = Keccak::v256();
@@ -17,9 +39,73 @@ pub fn keccak<T: AsRef<[u8]>>(preimage: T) -> [u8; 32] {
     }
 }
 
+/// Computes the SHA-256 hash, as does the EVM's precompile at address `0x02`.
+pub fn sha256<T: AsRef<[u8]>>(preimage: T) -> [u8; 32] {
+    Sha256::digest(preimage.as_ref()).into()
+}
+
+/// Computes the RIPEMD-160 hash, as does the EVM's precompile at address `0x03`.
+pub fn ripemd160<T: AsRef<[u8]>>(preimage: T) -> [u8; 20] {
+    Ripemd160::digest(preimage.as_ref()).into()
+}
+
 pub fn siphash(preimage: &[u8], key: &[u8; 16]) -> u64 {
     use std::hash::Hasher;
     let mut hasher = SipHasher24::new_with_key(key);
     hasher.write(preimage);
     hasher.finish()
 }
+
+#[test]
+fn test_hash_algos() {
+    let check = |algo: HashAlgo, preimage: &[u8], digest: &str| {
+        assert_eq!(hex::encode(algo.hash(preimage)), digest);
+    };
+    let empty = [
+        "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        "0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8",
+    ];
+    let abc = [
+        "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319",
+    ];
+    let algos = [HashAlgo::Keccak256, HashAlgo::Sha256, HashAlgo::Blake2b256];
+    for (i, algo) in algos.into_iter().enumerate() {
+        check(algo, b"", empty[i]);
+        check(algo, b"abc", abc[i]);
+    }
+}
+
+#[test]
+fn test_sha256() {
+    let check = |preimage: &[u8], digest: &str| {
+        assert_eq!(hex::encode(sha256(preimage)), digest);
+    };
+    check(
+        b"",
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+    );
+    check(
+        b"abc",
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+    );
+    check(
+        b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+    );
+}
+
+#[test]
+fn test_ripemd160() {
+    let check = |preimage: &[u8], digest: &str| {
+        assert_eq!(hex::encode(ripemd160(preimage)), digest);
+    };
+    check(b"", "9c1185a5c5e9fc54612808977ee8f548b2258d31");
+    check(b"abc", "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc");
+    check(
+        b"message digest",
+        "5d0689ef49d2fae572b881b123a85ffa21595f36",
+    );
+}
@@ -0,0 +1,387 @@
+// Copyright 2024, Offchain Labs, Inc.
+// For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+use crate::{Bytes20, Bytes32};
+use eyre::{bail, eyre, Result};
+
+/// An element of an ABI-encoded head.
+enum HeadSlot {
+    /// A static value, encoded in place.
+    Word(Bytes32),
+    /// A dynamic value, encoded as an offset into the tail.
+    Offset(usize),
+}
+
+/// Where an [`OutputWriter`] places the encoded tuple.
+pub trait OutputSink {
+    fn append(&mut self, data: &[u8]);
+}
+
+impl OutputSink for &mut Vec<u8> {
+    fn append(&mut self, data: &[u8]) {
+        self.extend_from_slice(data);
+    }
+}
+
+/// ABI-encodes a tuple of values, appending the result to an output sink on [`finish`].
+///
+/// [`finish`]: OutputWriter::finish
+pub struct OutputWriter<S: OutputSink> {
+    sink: S,
+    head: Vec<HeadSlot>,
+    tail: Vec<u8>,
+}
+
+impl<S: OutputSink> OutputWriter<S> {
+    pub fn new(sink: S) -> Self {
+        Self {
+            sink,
+            head: vec![],
+            tail: vec![],
+        }
+    }
+
+    /// Appends a `uint256`, or any other value already encoded as a word.
+    pub fn write_u256(&mut self, value: Bytes32) -> &mut Self {
+        self.head.push(HeadSlot::Word(value));
+        self
+    }
+
+    /// Appends an `address`, left-padded to a word.
+    pub fn write_address(&mut self, address: Bytes20) -> &mut Self {
+        let mut word = Bytes32::default();
+        word[12..].copy_from_slice(&address.0);
+        self.write_u256(word)
+    }
+
+    /// Appends a dynamic `bytes`, whose length and contents are placed in the tail.
+    pub fn write_bytes(&mut self, data: &[u8]) -> &mut Self {
+        self.head.push(HeadSlot::Offset(self.tail.len()));
+        self.tail.extend(word_from_usize(data.len()));
+        self.tail.extend(data);
+        self.tail.resize(self.tail.len().next_multiple_of(32), 0);
+        self
+    }
+
+    /// Writes the encoded tuple to the sink in a single append.
+    pub fn finish(mut self) {
+        let head_len = 32 * self.head.len();
+        let mut data = Vec::with_capacity(head_len + self.tail.len());
+        for slot in self.head {
+            let word = match slot {
+                HeadSlot::Word(word) => word,
+                HeadSlot::Offset(offset) => word_from_usize(head_len + offset),
+            };
+            data.extend(word);
+        }
+        data.extend(self.tail);
+        self.sink.append(&data);
+    }
+}
+
+/// The selector of Solidity's `Error(string)`, which prefixes the data of a revert with a reason.
+pub const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// Encodes a revert reason as Solidity's `Error(string)` does.
+pub fn encode_revert_reason(reason: &str) -> Vec<u8> {
+    let mut data = ERROR_SELECTOR.to_vec();
+    let mut writer = OutputWriter::new(&mut data);
+    writer.write_bytes(reason.as_bytes());
+    writer.finish();
+    data
+}
+
+/// Decodes ABI-encoded calldata, which consists of a 4-byte selector followed by a tuple of values.
+pub struct CalldataReader<'a> {
+    data: &'a [u8],
+    cursor: usize,
+}
+
+impl<'a> CalldataReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, cursor: 0 }
+    }
+
+    /// Reads the function selector.
+    pub fn selector(&self) -> Result<[u8; 4]> {
+        match self.data.get(..4) {
+            Some(selector) => Ok(selector.try_into().unwrap()),
+            None => bail!("calldata of {} bytes has no selector", self.data.len()),
+        }
+    }
+
+    /// Reads the next `uint256`, or any other value encoded as a single word.
+    pub fn read_u256(&mut self) -> Result<Bytes32> {
+        let word = self.word_at(self.cursor)?;
+        self.cursor += 32;
+        Ok(word)
+    }
+
+    /// Reads the next `address`, which must be left-padded with zeros.
+    pub fn read_address(&mut self) -> Result<Bytes20> {
+        let word = self.read_u256()?;
+        if word[..12].iter().any(|&x| x != 0) {
+            bail!("malformed address {word}");
+        }
+        Ok(word[12..].try_into().unwrap())
+    }
+
+    /// Reads the next dynamic `bytes`, following its offset into the tail.
+    pub fn read_bytes(&mut self) -> Result<Vec<u8>> {
+        let offset = word_to_usize(self.read_u256()?)?;
+        let len = word_to_usize(self.word_at(offset)?)?;
+        let start = offset.saturating_add(32);
+        let body = self.body()?;
+        match body.get(start..start.saturating_add(len)) {
+            Some(data) => Ok(data.to_vec()),
+            None => bail!("bytes of length {len} at offset {offset} exceed the calldata"),
+        }
+    }
+
+    /// The encoded tuple, which follows the selector.
+    fn body(&self) -> Result<&'a [u8]> {
+        self.selector()?;
+        Ok(&self.data[4..])
+    }
+
+    fn word_at(&self, offset: usize) -> Result<Bytes32> {
+        let body = self.body()?;
+        let word = body.get(offset..offset.saturating_add(32));
+        let word = word.ok_or_else(|| eyre!("calldata truncated at offset {offset}"))?;
+        Ok(word.try_into().unwrap())
+    }
+}
+
+/// A type in a function signature, as understood by [`validate`].
+enum AbiType {
+    /// A static value, encoded as a single word.
+    Word(WordKind),
+    Bytes,
+    String,
+    /// A dynamically-sized array of the given type.
+    Array(Box<AbiType>),
+}
+
+enum WordKind {
+    Uint(usize),
+    Int(usize),
+    Address,
+    Bool,
+    FixedBytes(usize),
+}
+
+impl AbiType {
+    fn parse(name: &str) -> Result<Self> {
+        if let Some(elem) = name.strip_suffix("[]") {
+            return Ok(Self::Array(Box::new(Self::parse(elem)?)));
+        }
+        let bits = |digits: &str| match digits {
+            "" => Some(256),
+            _ => digits
+                .parse::<usize>()
+                .ok()
+                .filter(|x| x % 8 == 0 && (8..=256).contains(x)),
+        };
+        let kind = match name {
+            "bytes" => return Ok(Self::Bytes),
+            "string" => return Ok(Self::String),
+            "address" => Some(WordKind::Address),
+            "bool" => Some(WordKind::Bool),
+            _ if name.starts_with("uint") => bits(&name[4..]).map(WordKind::Uint),
+            _ if name.starts_with("int") => bits(&name[3..]).map(WordKind::Int),
+            _ if name.starts_with("bytes") => {
+                let len = name[5..]
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|x| (1..=32).contains(x));
+                len.map(WordKind::FixedBytes)
+            }
+            _ => None,
+        };
+        match kind {
+            Some(kind) => Ok(Self::Word(kind)),
+            None => bail!("unsupported type {name}"),
+        }
+    }
+}
+
+impl WordKind {
+    /// Ensures the word is a canonical encoding, such as by rejecting dirty padding.
+    fn check(&self, word: Bytes32) -> Result<()> {
+        let zeros = |range: &[u8]| range.iter().all(|&x| x == 0);
+        let valid = match self {
+            Self::Uint(bits) => zeros(&word[..32 - bits / 8]),
+            Self::Int(bits) => {
+                let start = 32 - bits / 8;
+                let sign = if word[start] & 0x80 != 0 { 0xff } else { 0 };
+                word[..start].iter().all(|&x| x == sign)
+            }
+            Self::Address => zeros(&word[..12]),
+            Self::Bool => zeros(&word[..31]) && word[31] <= 1,
+            Self::FixedBytes(len) => zeros(&word[*len..]),
+        };
+        if !valid {
+            bail!("malformed word {word}");
+        }
+        Ok(())
+    }
+}
+
+/// Checks that `data` is a valid ABI encoding of the tuple of types in a signature, such as
+/// `(uint256,bytes)`. Supports elementary types, `bytes`, `string`, and dynamic arrays thereof.
+pub fn validate(signature: &str, data: &[u8]) -> Result<()> {
+    let list = signature
+        .strip_prefix('(')
+        .and_then(|x| x.strip_suffix(')'));
+    let list = list.unwrap_or(signature);
+    let mut types = vec![];
+    if !list.is_empty() {
+        for name in list.split(',') {
+            types.push(AbiType::parse(name.trim())?);
+        }
+    }
+    for (index, ty) in types.iter().enumerate() {
+        validate_slot(ty, data, index)?;
+    }
+    Ok(())
+}
+
+/// Validates the `index`th head slot of the tuple encoded in `data`.
+fn validate_slot(ty: &AbiType, data: &[u8], index: usize) -> Result<()> {
+    let word = word_in(data, index.saturating_mul(32))?;
+    let offset = match ty {
+        AbiType::Word(kind) => return kind.check(word),
+        _ => word_to_usize(word)?,
+    };
+    let tail = data.get(offset..);
+    let tail = tail.ok_or_else(|| eyre!("offset {offset} exceeds the {} byte data", data.len()))?;
+    let len = word_to_usize(word_in(tail, 0)?)?;
+    let body = &tail[32..];
+    match ty {
+        AbiType::Array(elem) => {
+            for index in 0..len {
+                validate_slot(elem, body, index)?;
+            }
+        }
+        _ => {
+            let content = body.get(..len);
+            let content = content.ok_or_else(|| eyre!("{len} bytes exceed the data"))?;
+            if matches!(ty, AbiType::String) {
+                std::str::from_utf8(content)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn word_in(data: &[u8], offset: usize) -> Result<Bytes32> {
+    let word = data.get(offset..offset.saturating_add(32));
+    let word = word.ok_or_else(|| eyre!("data truncated at offset {offset}"))?;
+    Ok(word.try_into().unwrap())
+}
+
+fn word_to_usize(word: Bytes32) -> Result<usize> {
+    if word[..24].iter().any(|&x| x != 0) {
+        bail!("value {word} is too large");
+    }
+    let value = u64::from_be_bytes(word[24..].try_into().unwrap());
+    usize::try_from(value).map_err(|_| eyre!("value {value} is too large"))
+}
+
+fn word_from_usize(value: usize) -> Bytes32 {
+    let mut word = Bytes32::default();
+    word[24..].copy_from_slice(&(value as u64).to_be_bytes());
+    word
+}
+
+#[test]
+fn test_output_writer() {
+    let mut outs = vec![];
+    let mut writer = OutputWriter::new(&mut outs);
+    writer
+        .write_address(Bytes20([0x11; 20]))
+        .write_u256(word_from_usize(42))
+        .write_bytes(b"hello");
+    writer.finish();
+
+    // abi.encode(address(0x1111...), uint256(42), bytes("hello"))
+    let expected = [
+        "0000000000000000000000001111111111111111111111111111111111111111",
+        "000000000000000000000000000000000000000000000000000000000000002a",
+        "0000000000000000000000000000000000000000000000000000000000000060",
+        "0000000000000000000000000000000000000000000000000000000000000005",
+        "68656c6c6f000000000000000000000000000000000000000000000000000000",
+    ];
+    assert_eq!(hex::encode(outs), expected.concat());
+}
+
+#[test]
+fn test_calldata_reader() {
+    // transfer(address,uint256,bytes) with the arguments of the output writer test
+    let calldata = [
+        "deadbeef",
+        "0000000000000000000000001111111111111111111111111111111111111111",
+        "000000000000000000000000000000000000000000000000000000000000002a",
+        "0000000000000000000000000000000000000000000000000000000000000060",
+        "0000000000000000000000000000000000000000000000000000000000000005",
+        "68656c6c6f000000000000000000000000000000000000000000000000000000",
+    ];
+    let calldata = hex::decode(calldata.concat()).unwrap();
+
+    let mut reader = CalldataReader::new(&calldata);
+    assert_eq!(reader.selector().unwrap(), [0xde, 0xad, 0xbe, 0xef]);
+    assert_eq!(reader.read_address().unwrap(), Bytes20([0x11; 20]));
+    assert_eq!(reader.read_u256().unwrap(), word_from_usize(42));
+    assert_eq!(reader.read_bytes().unwrap(), b"hello");
+
+    // cut off partway through the bytes
+    let truncated = &calldata[..4 + 32 * 4 + 2];
+    let mut reader = CalldataReader::new(truncated);
+    reader.read_address().unwrap();
+    reader.read_u256().unwrap();
+    assert!(reader.read_bytes().is_err());
+
+    // too short for even a selector
+    assert!(CalldataReader::new(&[0xde, 0xad]).selector().is_err());
+    assert!(CalldataReader::new(&[0xde, 0xad]).read_u256().is_err());
+}
+
+#[test]
+fn test_validate() {
+    let mut outs = vec![];
+    let mut writer = OutputWriter::new(&mut outs);
+    writer
+        .write_address(Bytes20([0x11; 20]))
+        .write_u256(word_from_usize(42))
+        .write_bytes("hello".as_bytes());
+    writer.finish();
+
+    validate("(address,uint256,bytes)", &outs).unwrap();
+    validate("(address,uint8,string)", &outs).unwrap();
+    validate("(uint256)", &outs).unwrap();
+    validate("()", &outs).unwrap();
+
+    // cut off partway through the bytes
+    assert!(validate("(address,uint256,bytes)", &outs[..32 * 4 + 2]).is_err());
+    assert!(validate("(bytes)", &outs).is_err()); // the address is no offset
+
+    // values that don't fit their declared types
+    assert!(validate("(bool)", &outs[32..]).is_err());
+    assert!(validate("(bytes4)", &outs).is_err());
+    assert!(validate("(address,int8)", &outs).is_ok());
+    assert!(validate("(int8)", &[[0; 31].as_slice(), &[0x80]].concat()).is_err());
+    assert!(validate("(int8)", &[0xff; 32]).is_ok());
+    assert!(validate("(tuple)", &outs).is_err());
+
+    // abi.encode(new uint16[](2)) with elements 1 and 2
+    let array = [
+        "0000000000000000000000000000000000000000000000000000000000000020",
+        "0000000000000000000000000000000000000000000000000000000000000002",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+        "0000000000000000000000000000000000000000000000000000000000000002",
+    ];
+    let array = hex::decode(array.concat()).unwrap();
+    validate("(uint16[])", &array).unwrap();
+    assert!(validate("(bool[])", &array).is_err());
+    assert!(validate("(uint16[])", &array[..32 * 3]).is_err());
+}
@@ -3,7 +3,7 @@
 
 use crate::{evm::user::UserOutcomeKind, Bytes20, Bytes32};
 use eyre::Result;
-use num_enum::IntoPrimitive;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
 use std::sync::Arc;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, IntoPrimitive)]
@@ -26,7 +26,7 @@ impl From<u8> for EvmApiStatus {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, TryFromPrimitive)]
 #[repr(u32)]
 pub enum EvmApiMethod {
     GetBytes32,
@@ -44,6 +44,11 @@ pub enum EvmApiMethod {
     AccountCodeHash,
     AddPages,
     CaptureHostIO,
+    AccountCodeSize,
+    BlockHash,
+    PrefetchBytes32,
+    SelfDestruct,
+    FlushOutput,
 }
 
 /// This offset is added to EvmApiMethod when sending a request
@@ -51,6 +56,15 @@ pub enum EvmApiMethod {
 /// id below that offset, indicating program status
 pub const EVM_API_METHOD_REQ_OFFSET: u32 = 0x10000000;
 
+impl EvmApiMethod {
+    /// Decodes the type of a request, which is the method plus [`EVM_API_METHOD_REQ_OFFSET`].
+    /// Returns `None` for program statuses and unknown methods.
+    pub fn from_request_status(status: u32) -> Option<Self> {
+        let method = status.checked_sub(EVM_API_METHOD_REQ_OFFSET)?;
+        Self::try_from(method).ok()
+    }
+}
+
 /// Copies data from Go into Rust.
 /// Note: clone should not clone actual data, just the reader.
 pub trait DataReader: Clone + Send + 'static {
@@ -58,7 +72,7 @@ pub trait DataReader: Clone + Send + 'static {
 }
 
 /// Simple implementation for `DataReader`, in case data comes from a `Vec`.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct VecReader(Arc<Vec<u8>>);
 
 impl VecReader {
@@ -79,6 +93,10 @@ pub trait EvmApi<D: DataReader>: Send + 'static {
     /// Analogous to `vm.SLOAD`.
     fn get_bytes32(&mut self, key: Bytes32) -> (Bytes32, u64);
 
+    /// Loads the given keys into Stylus VM's cache of the EVM state trie in one batch, warming each.
+    /// Keys already in the cache are skipped. Returns the access cost in gas.
+    fn prefetch_bytes32(&mut self, keys: &[Bytes32]) -> u64;
+
     /// Stores the given value at the given key in Stylus VM's cache of the EVM state trie.
     /// Note that the actual values only get written after calls to `set_trie_slots`.
     fn cache_bytes32(&mut self, key: Bytes32, value: Bytes32) -> u64;
@@ -166,6 +184,16 @@ pub trait EvmApi<D: DataReader>: Send + 'static {
     /// Analogous to `vm.BALANCE`.
     fn account_balance(&mut self, address: Bytes20) -> (Bytes32, u64);
 
+    /// Destroys the current contract, sending its balance to the beneficiary.
+    /// Returns the access cost in gas beyond the opcode's static cost.
+    /// Analogous to `vm.SELFDESTRUCT`.
+    fn self_destruct(&mut self, beneficiary: Bytes20) -> Result<u64>;
+
+    /// Sends output to the host ahead of the program finishing.
+    /// The host prepends whatever was flushed to the program's final output.
+    /// Not analogous to any EVM opcode.
+    fn flush_output(&mut self, data: &[u8]) -> Result<()>;
+
     /// Returns the code and the access cost in gas.
     /// Analogous to `vm.EXTCODECOPY`.
     fn account_code(&mut self, address: Bytes20, gas_left: u64) -> (D, u64);
@@ -175,6 +203,16 @@ pub trait EvmApi<D: DataReader>: Send + 'static {
     /// Analogous to `vm.EXTCODEHASH`.
     fn account_codehash(&mut self, address: Bytes20) -> (Bytes32, u64);
 
+    /// Gets the size of the given address's code without loading it.
+    /// Returns the size and the access cost in gas.
+    /// Analogous to `vm.EXTCODESIZE`.
+    fn account_code_size(&mut self, address: Bytes20) -> (u32, u64);
+
+    /// Gets the hash of the given block, which should be one of the 256 most recent.
+    /// Returns the hash and the access cost in gas.
+    /// Analogous to `vm.BLOCKHASH`.
+    fn block_hash(&mut self, number: u64) -> (Bytes32, u64);
+
     /// Determines the cost in gas of allocating additional wasm pages.
     /// Note: has the side effect of updating Geth's memory usage tracker.
     /// Not analogous to any EVM opcode.
@@ -190,3 +228,19 @@ pub trait EvmApi<D: DataReader>: Send + 'static {
         end_ink: u64,
     );
 }
+
+#[test]
+fn test_request_status() {
+    let last = EvmApiMethod::FlushOutput as u32;
+    for value in 0..=last {
+        let status = value + EVM_API_METHOD_REQ_OFFSET;
+        let method = EvmApiMethod::from_request_status(status).unwrap();
+        assert_eq!(method as u32, value);
+    }
+
+    // unknown methods and program statuses
+    let unknown = last + 1 + EVM_API_METHOD_REQ_OFFSET;
+    assert!(EvmApiMethod::from_request_status(unknown).is_none());
+    assert!(EvmApiMethod::from_request_status(u32::MAX).is_none());
+    assert!(EvmApiMethod::from_request_status(UserOutcomeKind::Success as u32).is_none());
+}
@@ -0,0 +1,152 @@
+// Copyright 2024, Offchain Labs, Inc.
+// For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+//! The BLAKE2b compression function F, matching the EVM's precompile at address `0x09`.
+
+use eyre::{bail, Result};
+
+/// The length of the encoded state vector `h`.
+pub const STATE_LEN: usize = 64;
+
+/// The length of the encoded message block `m`.
+pub const MESSAGE_LEN: usize = 128;
+
+/// The length of the encoded offset counter `t`.
+pub const OFFSET_LEN: usize = 16;
+
+/// Runs `rounds` rounds of the compression function, whose inputs are little-endian encoded
+/// words as in EIP-152, returning the new state vector. The final block flag is `f`.
+pub fn compress(rounds: u32, h: &[u8], m: &[u8], t: &[u8], f: bool) -> Result<[u8; STATE_LEN]> {
+    if h.len() != STATE_LEN || m.len() != MESSAGE_LEN || t.len() != OFFSET_LEN {
+        let lens = [h.len(), m.len(), t.len()];
+        bail!(
+            "blake2f inputs must be {STATE_LEN}, {MESSAGE_LEN}, and {OFFSET_LEN} bytes: {lens:?}"
+        );
+    }
+    let mut state = words::<8>(h);
+    compress_words(rounds, &mut state, &words(m), words(t), f);
+
+    let mut output = [0; STATE_LEN];
+    for (chunk, word) in output.chunks_exact_mut(8).zip(state) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    Ok(output)
+}
+
+/// Decodes little-endian words, the length of which must already have been checked.
+fn words<const N: usize>(data: &[u8]) -> [u64; N] {
+    let mut words = [0; N];
+    for (word, chunk) in words.iter_mut().zip(data.chunks_exact(8)) {
+        *word = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    words
+}
+
+/// The initialization vector of BLAKE2b.
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+/// The message word permutations of BLAKE2b, which repeat every 10 rounds.
+const BLAKE2B_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// The compression function F over words, as specified in RFC 7693.
+fn compress_words(rounds: u32, h: &mut [u64; 8], m: &[u64; 16], t: [u64; 2], f: bool) {
+    let mut v = [0; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&BLAKE2B_IV);
+    v[12] ^= t[0];
+    v[13] ^= t[1];
+    if f {
+        v[14] = !v[14];
+    }
+
+    #[rustfmt::skip]
+    fn mix(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+        v[a] = v[a].wrapping_add(v[b]).wrapping_add(x); v[d] = (v[d] ^ v[a]).rotate_right(32);
+        v[c] = v[c].wrapping_add(v[d]);                 v[b] = (v[b] ^ v[c]).rotate_right(24);
+        v[a] = v[a].wrapping_add(v[b]).wrapping_add(y); v[d] = (v[d] ^ v[a]).rotate_right(16);
+        v[c] = v[c].wrapping_add(v[d]);                 v[b] = (v[b] ^ v[c]).rotate_right(63);
+    }
+
+    for round in 0..rounds as usize {
+        let s = &BLAKE2B_SIGMA[round % 10];
+        mix(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+        mix(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+        mix(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+        mix(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+        mix(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+        mix(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        mix(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+        mix(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+    }
+    for (i, word) in h.iter_mut().enumerate() {
+        *word ^= v[i] ^ v[i + 8];
+    }
+}
+
+#[test]
+fn test_compress() {
+    // the vectors of EIP-152, which hash "abc"
+    let h = hex::decode(
+        "48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5\
+         d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b",
+    )
+    .unwrap();
+    let mut m = vec![0; MESSAGE_LEN];
+    m[..3].copy_from_slice(b"abc");
+    let mut t = vec![0; OFFSET_LEN];
+    t[0] = 3;
+
+    let check = |rounds: u32, f: bool, expected: &str| {
+        let output = compress(rounds, &h, &m, &t, f).unwrap();
+        assert_eq!(hex::encode(output), expected);
+    };
+    check(
+        0,
+        true,
+        "08c9bcf367e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5\
+         d282e6ad7f520e511f6c3e2b8c68059b9442be0454267ce079217e1319cde05b",
+    );
+    check(
+        1,
+        true,
+        "b63a380cb2897d521994a85234ee2c181b5f844d2c624c002677e9703449d2fb\
+         a551b3a8333bcdf5f2f7e08993d53923de3d64fcc68c034e717b9293fed7a421",
+    );
+    check(
+        12,
+        true,
+        "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+         7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923",
+    );
+    check(
+        12,
+        false,
+        "75ab69d3190a562c51aef8d88f1c2775876944407270c42c9844252c26d28752\
+         98743e7f6d5ea2f2d3e8d226039cd31b4e426ac4f2d3d666a610c2116fde4735",
+    );
+
+    // malformed lengths
+    assert!(compress(12, &h[1..], &m, &t, true).is_err());
+    assert!(compress(12, &h, &m[1..], &t, true).is_err());
+    assert!(compress(12, &h, &m, &[0; 8], true).is_err());
+}
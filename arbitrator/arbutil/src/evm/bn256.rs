@@ -0,0 +1,169 @@
+// Copyright 2024, Offchain Labs, Inc.
+// For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+//! Operations on the bn256 curve, matching the EVM's precompiles at addresses `0x06` to `0x08`.
+
+use bn::{AffineG1, AffineG2, Fq, Fq2, Fr, Group, Gt, G1, G2};
+use eyre::{bail, eyre, Result};
+
+/// The length of an encoded point on G1.
+pub const G1_LEN: usize = 64;
+
+/// The length of an encoded scalar.
+pub const SCALAR_LEN: usize = 32;
+
+/// The length of an encoded point on G2.
+pub const G2_LEN: usize = 128;
+
+/// The length of each `(G1, G2)` pair in the input to a pairing check.
+pub const PAIR_LEN: usize = G1_LEN + G2_LEN;
+
+/// Adds two points on G1, as does the precompile at `0x06`.
+pub fn add(a: &[u8], b: &[u8]) -> Result<[u8; G1_LEN]> {
+    let a = read_g1(a)?;
+    let b = read_g1(b)?;
+    Ok(write_g1(a + b))
+}
+
+/// Multiplies a point on G1 by a scalar, as does the precompile at `0x07`.
+pub fn mul(point: &[u8], scalar: &[u8]) -> Result<[u8; G1_LEN]> {
+    let point = read_g1(point)?;
+    if scalar.len() != SCALAR_LEN {
+        bail!("scalar must be {SCALAR_LEN} bytes, not {}", scalar.len());
+    }
+    let scalar = Fr::from_slice(scalar).map_err(|_| eyre!("invalid scalar"))?;
+    Ok(write_g1(point * scalar))
+}
+
+/// Checks whether the product of the pairings of each `(G1, G2)` pair is one, as does the
+/// precompile at `0x08`. An empty input has no pairs and so passes the check.
+pub fn pairing(input: &[u8]) -> Result<bool> {
+    if input.len() % PAIR_LEN != 0 {
+        bail!(
+            "pairing input of {} bytes isn't a multiple of {PAIR_LEN}",
+            input.len()
+        );
+    }
+    let mut pairs = Vec::with_capacity(input.len() / PAIR_LEN);
+    for pair in input.chunks_exact(PAIR_LEN) {
+        let (a, b) = pair.split_at(G1_LEN);
+        pairs.push((read_g1(a)?, read_g2(b)?));
+    }
+    Ok(bn::pairing_batch(&pairs) == Gt::one())
+}
+
+fn read_fq(data: &[u8]) -> Result<Fq> {
+    Fq::from_slice(data).map_err(|_| eyre!("invalid field element"))
+}
+
+/// Reads a point on G1, where `(0, 0)` denotes the point at infinity.
+pub(crate) fn read_g1(data: &[u8]) -> Result<G1> {
+    if data.len() != G1_LEN {
+        bail!("point must be {G1_LEN} bytes, not {}", data.len());
+    }
+    let x = read_fq(&data[..32])?;
+    let y = read_fq(&data[32..])?;
+    if x == Fq::zero() && y == Fq::zero() {
+        return Ok(G1::zero());
+    }
+    let point = AffineG1::new(x, y).map_err(|_| eyre!("point not on curve"))?;
+    Ok(point.into())
+}
+
+/// Reads a point on G2, whose coordinates are each encoded imaginary part first. The point at
+/// infinity is encoded as zeros.
+fn read_g2(data: &[u8]) -> Result<G2> {
+    let x = Fq2::new(read_fq(&data[32..64])?, read_fq(&data[..32])?);
+    let y = Fq2::new(read_fq(&data[96..128])?, read_fq(&data[64..96])?);
+    if x == Fq2::zero() && y == Fq2::zero() {
+        return Ok(G2::zero());
+    }
+    let point = AffineG2::new(x, y).map_err(|_| eyre!("point not on twist"))?;
+    Ok(point.into())
+}
+
+/// Writes a point on G1, encoding the point at infinity as `(0, 0)`.
+fn write_g1(point: G1) -> [u8; G1_LEN] {
+    let mut output = [0; G1_LEN];
+    if let Some(point) = AffineG1::from_jacobian(point) {
+        point.x().to_big_endian(&mut output[..32]).unwrap();
+        point.y().to_big_endian(&mut output[32..]).unwrap();
+    }
+    output
+}
+
+#[cfg(test)]
+fn g1(x: &str, y: &str) -> Vec<u8> {
+    hex::decode(format!("{x:0>64}{y:0>64}")).unwrap()
+}
+
+#[test]
+fn test_add_and_mul() {
+    let gen = g1("1", "2");
+    let double = g1(
+        "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3",
+        "15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4",
+    );
+    let neg = g1(
+        "1",
+        "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45",
+    );
+    let zero = [0; G1_LEN];
+    let scalar = |x: u8| {
+        let mut scalar = [0; SCALAR_LEN];
+        scalar[31] = x;
+        scalar
+    };
+
+    assert_eq!(add(&gen, &gen).unwrap().to_vec(), double);
+    assert_eq!(mul(&gen, &scalar(2)).unwrap().to_vec(), double);
+
+    // the point at infinity
+    assert_eq!(add(&gen, &neg).unwrap(), zero);
+    assert_eq!(add(&zero, &zero).unwrap(), zero);
+    assert_eq!(add(&zero, &gen).unwrap().to_vec(), gen);
+    assert_eq!(mul(&gen, &scalar(0)).unwrap(), zero);
+    assert_eq!(mul(&zero, &scalar(2)).unwrap(), zero);
+
+    // malformed inputs
+    assert!(add(&gen, &gen[..63]).is_err());
+    assert!(add(&gen, &g1("1", "3")).is_err());
+    assert!(mul(&gen, &[2]).is_err());
+}
+
+#[test]
+fn test_pairing() {
+    let gen = g1("1", "2");
+    let neg = g1(
+        "1",
+        "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45",
+    );
+    let gen2 = hex::decode(
+        [
+            "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2",
+            "1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed",
+            "090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b",
+            "12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
+        ]
+        .concat(),
+    )
+    .unwrap();
+
+    // e(P, Q) * e(-P, Q) = 1
+    let valid = [&gen, &gen2, &neg, &gen2].concat();
+    assert!(pairing(&valid).unwrap());
+
+    // e(P, Q) * e(P, Q) != 1
+    let invalid = [&gen, &gen2, &gen, &gen2].concat();
+    assert!(!pairing(&invalid).unwrap());
+
+    // no pairs, and pairs involving the point at infinity
+    assert!(pairing(&[]).unwrap());
+    assert!(pairing(&[[0; G1_LEN].as_slice(), &gen2].concat()).unwrap());
+
+    // malformed inputs
+    assert!(pairing(&valid[..PAIR_LEN + 1]).is_err());
+    let mut off_twist = gen2.clone();
+    off_twist[127] ^= 1;
+    assert!(pairing(&[&gen, &off_twist].concat()).is_err());
+}
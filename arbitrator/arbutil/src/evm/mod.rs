@@ -1,10 +1,14 @@
 // Copyright 2023-2024, Offchain Labs, Inc.
 // For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
 
-use crate::{Bytes20, Bytes32};
+use crate::{crypto, Bytes20, Bytes32};
 
+pub mod abi;
 pub mod api;
+pub mod blake2f;
+pub mod bn256;
 pub mod req;
+pub mod rlp;
 pub mod storage;
 pub mod user;
 
@@ -35,6 +39,25 @@ pub const COPY_WORD_GAS: u64 = 3;
 pub const KECCAK_256_GAS: u64 = 30;
 pub const KECCAK_WORD_GAS: u64 = 6;
 
+// keccak256 of the empty string, the code hash of existing accounts with no code
+pub const EMPTY_CODE_HASH: Bytes32 = Bytes32([
+    0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
+    0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+]);
+
+// vm.GasExtStep (see jump_table.go)
+pub const BLOCKHASH_GAS: u64 = 20;
+
+// params.SelfdestructGasEIP150
+pub const SELFDESTRUCT_GAS: u64 = 5000;
+
+// the number of recent blocks whose hashes are available via BLOCKHASH
+pub const BLOCKHASH_WINDOW: u64 = 256;
+
+// params.TxDataZeroGas and params.TxDataNonZeroGasEIP2028
+pub const TX_DATA_ZERO_GAS: u64 = 4;
+pub const TX_DATA_NON_ZERO_GAS: u64 = 16;
+
 // vm.GasQuickStep (see gas.go)
 pub const GAS_QUICK_STEP: u64 = 2;
 
@@ -83,6 +106,7 @@ pub struct EvmData {
     pub block_gas_limit: u64,
     pub block_number: u64,
     pub block_timestamp: u64,
+    pub block_randomness: Bytes32,
     pub contract_address: Bytes20,
     pub module_hash: Bytes32,
     pub msg_sender: Bytes20,
@@ -93,9 +117,203 @@ pub struct EvmData {
     pub return_data_len: u32,
     pub cached: bool,
     pub tracing: bool,
+    pub read_only: bool,
+}
+
+/// A field whose value differs between two [`EvmData`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldDiff {
+    pub name: &'static str,
+    pub left: String,
+    pub right: String,
+}
+
+impl EvmData {
+    /// Lists the fields whose values differ from those of `other`, in declaration order.
+    /// Useful when tracking down nondeterminism between runs.
+    pub fn diff(&self, other: &EvmData) -> Vec<FieldDiff> {
+        let mut diffs = vec![];
+        macro_rules! compare {
+            ($($field:ident),* $(,)?) => {$(
+                if self.$field != other.$field {
+                    diffs.push(FieldDiff {
+                        name: stringify!($field),
+                        left: self.$field.to_string(),
+                        right: other.$field.to_string(),
+                    });
+                }
+            )*};
+        }
+        compare!(
+            block_basefee,
+            chainid,
+            block_coinbase,
+            block_gas_limit,
+            block_number,
+            block_timestamp,
+            block_randomness,
+            contract_address,
+            module_hash,
+            msg_sender,
+            msg_value,
+            tx_gas_price,
+            tx_origin,
+            reentrant,
+            return_data_len,
+            cached,
+            tracing,
+            read_only,
+        );
+        diffs
+    }
+}
+
+/// Whether the hash of block `number` is available while executing block `current`.
+/// Only the [`BLOCKHASH_WINDOW`] blocks preceding the current one qualify.
+pub fn block_hash_available(current: u64, number: u64) -> bool {
+    number < current && number >= current.saturating_sub(BLOCKHASH_WINDOW)
+}
+
+/// Computes the address of a contract deployed via `CREATE`, which is the tail of the hash of the
+/// RLP-encoded list `[deployer, nonce]`.
+pub fn compute_create_address(deployer: Bytes20, nonce: u64) -> Bytes20 {
+    let nonce_bytes = nonce.to_be_bytes();
+    let nonce_bytes = &nonce_bytes[nonce.leading_zeros() as usize / 8..];
+
+    let mut rlp = Vec::with_capacity(1 + 21 + 9);
+    let nonce_len = match nonce {
+        0..=0x7f => 1,
+        _ => 1 + nonce_bytes.len(),
+    };
+    rlp.push(0xc0 + (21 + nonce_len) as u8);
+    rlp.push(0x80 + 20);
+    rlp.extend(deployer);
+    match nonce {
+        0 => rlp.push(0x80),
+        1..=0x7f => rlp.push(nonce as u8),
+        _ => {
+            rlp.push(0x80 + nonce_bytes.len() as u8);
+            rlp.extend(nonce_bytes);
+        }
+    }
+    let hash = crypto::keccak(rlp);
+    Bytes20(hash[12..].try_into().unwrap())
+}
+
+/// Computes the address of a contract deployed via `CREATE2`, as specified by EIP-1014.
+pub fn compute_create2_address(deployer: Bytes20, salt: Bytes32, init_code: &[u8]) -> Bytes20 {
+    let mut preimage = Vec::with_capacity(1 + 20 + 32 + 32);
+    preimage.push(0xff);
+    preimage.extend(deployer);
+    preimage.extend(salt);
+    preimage.extend(crypto::keccak(init_code));
+    let hash = crypto::keccak(preimage);
+    Bytes20(hash[12..].try_into().unwrap())
 }
 
 /// Returns the minimum number of EVM words needed to store `bytes` bytes.
 pub fn evm_words(bytes: u32) -> u32 {
     crate::math::div_ceil::<32>(bytes as usize) as u32
 }
+
+/// Computes the intrinsic gas of a transaction's calldata, which depends on its zero bytes.
+pub fn calldata_gas(data: &[u8]) -> u64 {
+    let zeros = data.iter().filter(|&&x| x == 0).count() as u64;
+    let non_zeros = data.len() as u64 - zeros;
+    zeros * TX_DATA_ZERO_GAS + non_zeros * TX_DATA_NON_ZERO_GAS
+}
+
+#[test]
+fn test_calldata_gas() {
+    assert_eq!(calldata_gas(&[]), 0);
+    assert_eq!(calldata_gas(&[0; 3]), 12);
+    assert_eq!(calldata_gas(&[1, 0xff]), 32);
+    assert_eq!(
+        calldata_gas(&[0xa9, 0x05, 0x9c, 0xbb, 0, 0, 0, 0x01]),
+        5 * 16 + 3 * 4
+    );
+}
+
+#[test]
+fn test_block_hash_window() {
+    assert!(block_hash_available(1000, 999));
+    assert!(block_hash_available(1000, 744));
+    assert!(!block_hash_available(1000, 743));
+    assert!(!block_hash_available(1000, 1000));
+    assert!(!block_hash_available(1000, 1001));
+    assert!(block_hash_available(10, 0));
+    assert!(!block_hash_available(0, 0));
+}
+
+#[test]
+fn test_empty_code_hash() {
+    assert_eq!(EMPTY_CODE_HASH, crate::crypto::keccak(b"").into());
+}
+
+#[test]
+fn test_create_address() {
+    let deployer = hex::decode("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0").unwrap();
+    let deployer: Bytes20 = deployer.try_into().unwrap();
+    let expected = [
+        (0, "cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d"),
+        (1, "343c43a37d37dff08ae8c4a11544c718abb4fcf8"),
+        (2, "f778b86fa74e846c4f0a1fbd1335fe81c00a0c91"),
+        (3, "fffd933a0bc612844eaf0c6fe3e5b8e9b6c1d19c"),
+        (0x7f, "06d9a77f5e4b311bae8d559db9cdb4df94104aa0"),
+        (0x80, "08e190dcb7b73f5fcdabb43e102215c83659a76d"),
+        (u64::MAX, "9bc924993b60399df164c3763a964301d3db95ca"),
+    ];
+    for (nonce, address) in expected {
+        let computed = compute_create_address(deployer, nonce);
+        assert_eq!(hex::encode(computed), address, "nonce {nonce}");
+    }
+}
+
+#[test]
+fn test_create2_address() {
+    let case = |deployer: &str, salt: &str, init_code: &str| {
+        let deployer = hex::decode(deployer).unwrap().try_into().unwrap();
+        let salt = hex::decode(format!("{salt:0>64}"))
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let init_code = hex::decode(init_code).unwrap();
+        hex::encode(compute_create2_address(deployer, salt, &init_code))
+    };
+
+    // examples from EIP-1014
+    let zero = "0000000000000000000000000000000000000000";
+    let high = "deadbeef00000000000000000000000000000000";
+    let low = "00000000000000000000000000000000deadbeef";
+    assert_eq!(
+        case(zero, "00", "00"),
+        "4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38"
+    );
+    assert_eq!(
+        case(high, "00", "00"),
+        "b928f69bb1d91cd65274e3c79d8986362984fda3"
+    );
+    assert_eq!(
+        case(low, "cafebabe", "deadbeef"),
+        "60f3f640a8508fc6a86d45df051962668e1e8ac7"
+    );
+    assert_eq!(
+        case(zero, "00", ""),
+        "e33c0c7f7df4809055c3eba6c09cfe4baf1bd9e0"
+    );
+}
+
+#[test]
+fn test_evm_data_diff() {
+    let left = EvmData::default();
+    assert!(left.diff(&left).is_empty());
+
+    let mut right = left;
+    right.block_number = 42;
+    let expected = FieldDiff {
+        name: "block_number",
+        left: "0".into(),
+        right: "42".into(),
+    };
+    assert_eq!(left.diff(&right), vec![expected]);
+}
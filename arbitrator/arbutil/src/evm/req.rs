@@ -3,18 +3,453 @@
 
 use crate::{
     evm::{
-        api::{DataReader, EvmApi, EvmApiMethod, EvmApiStatus},
+        api::{DataReader, EvmApi, EvmApiMethod, EvmApiStatus, VecReader},
         storage::{StorageCache, StorageWord},
         user::UserOutcomeKind,
     },
     pricing::EVM_API_INK,
-    Bytes20, Bytes32,
+    Bytes20, Bytes32, MemoryBoundsError,
 };
 use eyre::{bail, eyre, Result};
-use std::collections::hash_map::Entry;
+use fnv::FnvHashMap as HashMap;
+use std::{
+    collections::hash_map::Entry,
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+/// Answered in place of a request's gas cost when the request ran out of gas.
+pub const OUT_OF_GAS: u64 = u64::MAX;
 
 pub trait RequestHandler<D: DataReader>: Send + 'static {
     fn request(&mut self, req_type: EvmApiMethod, req_data: impl AsRef<[u8]>) -> (Vec<u8>, D, u64);
+
+    /// Like [`request`], but for bridges that may fail to deliver the request.
+    ///
+    /// [`request`]: RequestHandler::request
+    fn try_request(
+        &mut self,
+        req_type: EvmApiMethod,
+        req_data: impl AsRef<[u8]>,
+    ) -> Result<(Vec<u8>, D, u64), RequestError> {
+        Ok(self.request(req_type, req_data))
+    }
+
+    /// Completes any buffered work. Called before a program's output is extracted.
+    fn flush(&mut self) -> Result<(), RequestError> {
+        Ok(())
+    }
+}
+
+/// Reasons a request may fail, whether refused before reaching the EVM or rejected by it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequestError {
+    /// Issuing the request would dip into the gas held in reserve.
+    BelowGasReserve { gas_left: u64, reserve: u64 },
+    /// The gas consumed across all requests has passed the deadline.
+    DeadlineExceeded { gas_used: u64, deadline: u64 },
+    /// The host couldn't service the request right now, though it may later.
+    Unavailable,
+    /// The host answered a different request than the one outstanding.
+    IdMismatch { expected: u32, actual: u32 },
+    /// The request's payload is longer than allowed.
+    OversizedPayload { len: usize, limit: usize },
+    /// The host's answer is longer than allowed.
+    OversizedResponse { len: usize, limit: usize },
+    /// The request's payload, with those of other unanswered requests, exceeds the budget.
+    RequestBudgetExceeded {
+        in_flight: usize,
+        len: usize,
+        budget: usize,
+    },
+    /// The program has already made as many requests as allowed.
+    RequestLimitExceeded { limit: u32 },
+    /// The program ran past its wall-clock deadline.
+    Timeout,
+    /// The request ran out of gas while the host was carrying it out.
+    OutOfGas,
+    /// The host carried out the request but reported a failure.
+    Host(EvmApiStatus),
+    /// The request referred to memory that's out of bounds.
+    Memory(MemoryBoundsError),
+}
+
+impl RequestError {
+    /// Refuses requests when less than `reserve` gas is left. A reserve of `0` never refuses.
+    pub fn check_gas_reserve(gas_left: u64, reserve: u64) -> Result<(), Self> {
+        if gas_left < reserve {
+            return Err(Self::BelowGasReserve { gas_left, reserve });
+        }
+        Ok(())
+    }
+
+    /// Refuses requests once more than `deadline` gas has been used across all prior requests.
+    /// A deadline of `0` never refuses.
+    pub fn check_deadline(gas_used: u64, deadline: u64) -> Result<(), Self> {
+        if deadline != 0 && gas_used > deadline {
+            return Err(Self::DeadlineExceeded { gas_used, deadline });
+        }
+        Ok(())
+    }
+
+    /// Ensures the host is answering the outstanding request.
+    pub fn check_id(expected: u32, actual: u32) -> Result<(), Self> {
+        if expected != actual {
+            return Err(Self::IdMismatch { expected, actual });
+        }
+        Ok(())
+    }
+
+    /// Refuses payloads longer than `limit` bytes. A limit of `0` never refuses.
+    pub fn check_payload_len(len: usize, limit: usize) -> Result<(), Self> {
+        if limit != 0 && len > limit {
+            return Err(Self::OversizedPayload { len, limit });
+        }
+        Ok(())
+    }
+
+    /// Rejects answers longer than `limit` bytes. A limit of `0` never rejects.
+    pub fn check_response_len(len: usize, limit: usize) -> Result<(), Self> {
+        if limit != 0 && len > limit {
+            return Err(Self::OversizedResponse { len, limit });
+        }
+        Ok(())
+    }
+
+    /// Refuses payloads that would raise the bytes held by unanswered requests past `budget`.
+    /// A budget of `0` never refuses.
+    pub fn check_request_budget(in_flight: usize, len: usize, budget: usize) -> Result<(), Self> {
+        if budget != 0 && in_flight.saturating_add(len) > budget {
+            return Err(Self::RequestBudgetExceeded {
+                in_flight,
+                len,
+                budget,
+            });
+        }
+        Ok(())
+    }
+
+    /// Refuses requests once `made` requests have reached the `limit`. A limit of `0` never refuses.
+    pub fn check_request_count(made: u32, limit: u32) -> Result<(), Self> {
+        if limit != 0 && made >= limit {
+            return Err(Self::RequestLimitExceeded { limit });
+        }
+        Ok(())
+    }
+
+    /// Refuses requests made at or after the `deadline`, if there is one.
+    pub fn check_timeout(now: Instant, deadline: Option<Instant>) -> Result<(), Self> {
+        match deadline {
+            Some(deadline) if now >= deadline => Err(Self::Timeout),
+            _ => Ok(()),
+        }
+    }
+
+    /// Interprets the gas cost the host answers a request with, which may be [`OUT_OF_GAS`].
+    pub fn check_gas_cost(gas: u64) -> Result<u64, Self> {
+        match gas {
+            OUT_OF_GAS => Err(Self::OutOfGas),
+            gas => Ok(gas),
+        }
+    }
+
+    /// Interprets the status byte the host answers some requests with.
+    pub fn check_status(res: &[u8]) -> Result<(), Self> {
+        let status = res.first().copied().map(EvmApiStatus::from);
+        match status.unwrap_or(EvmApiStatus::Failure) {
+            EvmApiStatus::Success => Ok(()),
+            status => Err(Self::Host(status)),
+        }
+    }
+
+    /// Whether the same request may succeed if issued again.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Unavailable)
+    }
+}
+
+impl From<MemoryBoundsError> for RequestError {
+    fn from(error: MemoryBoundsError) -> Self {
+        Self::Memory(error)
+    }
+}
+
+impl std::error::Error for RequestError {}
+
+impl Display for RequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BelowGasReserve { gas_left, reserve } => {
+                write!(f, "{gas_left} gas left is below the {reserve} gas reserve")
+            }
+            Self::DeadlineExceeded { gas_used, deadline } => {
+                write!(f, "{gas_used} request gas is past the {deadline} deadline")
+            }
+            Self::Unavailable => write!(f, "host temporarily unavailable"),
+            Self::IdMismatch { expected, actual } => {
+                write!(f, "host answered request {actual} instead of {expected}")
+            }
+            Self::OversizedPayload { len, limit } => {
+                write!(f, "payload of {len} bytes exceeds the {limit} byte limit")
+            }
+            Self::OversizedResponse { len, limit } => {
+                write!(f, "answer of {len} bytes exceeds the {limit} byte limit")
+            }
+            Self::RequestBudgetExceeded {
+                in_flight,
+                len,
+                budget,
+            } => write!(
+                f,
+                "{in_flight} + {len} request bytes exceed the {budget} byte budget"
+            ),
+            Self::RequestLimitExceeded { limit } => write!(f, "request limit of {limit} reached"),
+            Self::Timeout => write!(f, "execution timed out"),
+            Self::OutOfGas => write!(f, "request ran out of gas"),
+            Self::Host(status) => write!(f, "{status:?}"),
+            Self::Memory(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+/// Wraps a handler, retrying requests that fail with a retryable [`RequestError`].
+pub struct RetryingRequestHandler<H> {
+    handler: H,
+    retries: u32,
+    backoff: Duration,
+}
+
+impl<H> RetryingRequestHandler<H> {
+    /// Retries up to `retries` times, waiting `backoff` before the first retry and twice as long
+    /// before each one after.
+    pub fn new(handler: H, retries: u32, backoff: Duration) -> Self {
+        Self {
+            handler,
+            retries,
+            backoff,
+        }
+    }
+
+    pub fn inner(&mut self) -> &mut H {
+        &mut self.handler
+    }
+}
+
+impl<D: DataReader, H: RequestHandler<D>> RequestHandler<D> for RetryingRequestHandler<H> {
+    /// Traps once out of retries, as the answer can't carry the error. [`EvmApiRequestor`] uses
+    /// [`try_request`] instead wherever the request may fail.
+    ///
+    /// [`try_request`]: RequestHandler::try_request
+    fn request(&mut self, req_type: EvmApiMethod, req_data: impl AsRef<[u8]>) -> (Vec<u8>, D, u64) {
+        match self.try_request(req_type, req_data) {
+            Ok(response) => response,
+            Err(error) => panic!("{req_type:?} request failed: {error}"),
+        }
+    }
+
+    fn try_request(
+        &mut self,
+        req_type: EvmApiMethod,
+        req_data: impl AsRef<[u8]>,
+    ) -> Result<(Vec<u8>, D, u64), RequestError> {
+        let req_data = req_data.as_ref();
+        let mut backoff = self.backoff;
+        let mut retries = 0;
+        loop {
+            match self.handler.try_request(req_type, req_data) {
+                Err(error) if error.is_retryable() && retries < self.retries => {
+                    std::thread::sleep(backoff);
+                    backoff = backoff.saturating_mul(2);
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), RequestError> {
+        self.handler.flush()
+    }
+}
+
+/// A fault [`ChaosRequestHandler`] may inject into an answer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// Cuts the result short, keeping at most this many bytes.
+    Truncate(usize),
+    /// Flips a pseudo-random bit of the result.
+    Corrupt,
+    /// Waits this long before answering.
+    Delay(Duration),
+}
+
+/// Wraps a handler, injecting faults into its answers to test how programs cope with a
+/// misbehaving host. Faults are drawn from a seeded generator, so a seed always yields the same
+/// sequence of faults.
+pub struct ChaosRequestHandler<H> {
+    handler: H,
+    /// Each fault and the `n` for which it's injected into one in `n` answers.
+    faults: Vec<(Fault, u32)>,
+    state: u64,
+}
+
+impl<H> ChaosRequestHandler<H> {
+    /// Injects no faults until some are added.
+    pub fn new(handler: H, seed: u64) -> Self {
+        Self {
+            handler,
+            faults: vec![],
+            state: seed,
+        }
+    }
+
+    /// Injects `fault` into one in `one_in` answers on average, or every answer if `1`.
+    /// Faults are applied in the order added.
+    pub fn add_fault(&mut self, fault: Fault, one_in: u32) {
+        self.faults.push((fault, one_in.max(1)));
+    }
+
+    pub fn inner(&mut self) -> &mut H {
+        &mut self.handler
+    }
+
+    /// Advances the generator, which is SplitMix64.
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn inject(&mut self, res: &mut Vec<u8>) {
+        for (fault, one_in) in self.faults.clone() {
+            if self.next() % one_in as u64 != 0 {
+                continue;
+            }
+            match fault {
+                Fault::Truncate(len) => res.truncate(len),
+                Fault::Corrupt if !res.is_empty() => {
+                    let bit = self.next() % (8 * res.len() as u64);
+                    res[bit as usize / 8] ^= 1 << (bit % 8);
+                }
+                Fault::Corrupt => {}
+                Fault::Delay(delay) => std::thread::sleep(delay),
+            }
+        }
+    }
+}
+
+impl<D: DataReader, H: RequestHandler<D>> RequestHandler<D> for ChaosRequestHandler<H> {
+    fn request(&mut self, req_type: EvmApiMethod, req_data: impl AsRef<[u8]>) -> (Vec<u8>, D, u64) {
+        let (mut res, data, gas) = self.handler.request(req_type, req_data);
+        self.inject(&mut res);
+        (res, data, gas)
+    }
+
+    fn try_request(
+        &mut self,
+        req_type: EvmApiMethod,
+        req_data: impl AsRef<[u8]>,
+    ) -> Result<(Vec<u8>, D, u64), RequestError> {
+        let (mut res, data, gas) = self.handler.try_request(req_type, req_data)?;
+        self.inject(&mut res);
+        Ok((res, data, gas))
+    }
+
+    fn flush(&mut self) -> Result<(), RequestError> {
+        self.handler.flush()
+    }
+}
+
+/// A handler that executes nothing, instead recording each request and answering it with zeros
+/// or a configured default. Useful for static analysis and gas estimation.
+pub struct DryRunRequestHandler {
+    gas: u64,
+    requests: Vec<(EvmApiMethod, Vec<u8>)>,
+    default_answer: Option<Vec<u8>>,
+}
+
+impl DryRunRequestHandler {
+    /// Charges `gas` for every request.
+    pub fn new(gas: u64) -> Self {
+        Self {
+            gas,
+            requests: vec![],
+            default_answer: None,
+        }
+    }
+
+    /// Answers every request with `answer` rather than zeros. Handlers that reach the EVM have no
+    /// such hook, since an answer the EVM didn't give would make replay diverge from native
+    /// execution.
+    pub fn set_default_answer(&mut self, answer: Vec<u8>) {
+        self.default_answer = Some(answer);
+    }
+
+    /// The requests made so far, in order.
+    pub fn requests(&self) -> &[(EvmApiMethod, Vec<u8>)] {
+        &self.requests
+    }
+}
+
+impl RequestHandler<VecReader> for DryRunRequestHandler {
+    fn request(
+        &mut self,
+        req_type: EvmApiMethod,
+        req_data: impl AsRef<[u8]>,
+    ) -> (Vec<u8>, VecReader, u64) {
+        self.requests.push((req_type, req_data.as_ref().to_vec()));
+        let answer = self.default_answer.clone().unwrap_or_else(|| vec![0; 32]);
+        (answer, VecReader::new(vec![]), self.gas)
+    }
+}
+
+/// The wall-clock time spent servicing requests, by method.
+#[derive(Clone, Debug, Default)]
+pub struct MethodTimings(HashMap<EvmApiMethod, Duration>);
+
+impl MethodTimings {
+    /// Attributes `elapsed` to servicing a request of the given method.
+    pub fn record(&mut self, method: EvmApiMethod, elapsed: Duration) {
+        *self.0.entry(method).or_default() += elapsed;
+    }
+
+    /// The total time spent servicing requests of the given method.
+    pub fn get(&self, method: EvmApiMethod) -> Duration {
+        self.0.get(&method).copied().unwrap_or_default()
+    }
+
+    /// The total time spent on each method that's been requested.
+    pub fn iter(&self) -> impl Iterator<Item = (EvmApiMethod, Duration)> + '_ {
+        self.0.iter().map(|(&method, &elapsed)| (method, elapsed))
+    }
+}
+
+/// The number of requests made, by method.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RequestStats(HashMap<EvmApiMethod, u32>);
+
+impl RequestStats {
+    /// Counts a request of the given method.
+    pub fn record(&mut self, method: EvmApiMethod) {
+        *self.0.entry(method).or_default() += 1;
+    }
+
+    /// The number of requests of the given method.
+    pub fn get(&self, method: EvmApiMethod) -> u32 {
+        self.0.get(&method).copied().unwrap_or_default()
+    }
+
+    /// The number of requests across all methods.
+    pub fn total(&self) -> u32 {
+        self.0.values().sum()
+    }
+
+    /// The count of each method that's been requested.
+    pub fn iter(&self) -> impl Iterator<Item = (EvmApiMethod, u32)> + '_ {
+        self.0.iter().map(|(&method, &count)| (method, count))
+    }
 }
 
 pub struct EvmApiRequestor<D: DataReader, H: RequestHandler<D>> {
@@ -38,6 +473,20 @@ impl<D: DataReader, H: RequestHandler<D>> EvmApiRequestor<D, H> {
         self.handler.request(req_type, req_data)
     }
 
+    /// Issues a request that may fail, for methods that can report the failure.
+    fn try_request(
+        &mut self,
+        req_type: EvmApiMethod,
+        req_data: impl AsRef<[u8]>,
+    ) -> Result<(Vec<u8>, D, u64), RequestError> {
+        self.handler.try_request(req_type, req_data)
+    }
+
+    /// Completes any work buffered by the underlying handler.
+    pub fn flush(&mut self) -> Result<(), RequestError> {
+        self.handler.flush()
+    }
+
     /// Call out to a contract.
     fn call_request(
         &mut self,
@@ -66,6 +515,16 @@ impl<D: DataReader, H: RequestHandler<D>> EvmApiRequestor<D, H> {
         &mut self.handler
     }
 
+    /// Provides the underlying handler without the ability to issue requests.
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+
+    /// Provides the storage slots read and written so far, awaiting a flush.
+    pub fn storage_cache(&self) -> &StorageCache {
+        &self.storage_cache
+    }
+
     fn create_request(
         &mut self,
         create_type: EvmApiMethod,
@@ -82,7 +541,10 @@ impl<D: DataReader, H: RequestHandler<D>> EvmApiRequestor<D, H> {
         }
         request.extend(code);
 
-        let (mut res, data, cost) = self.request(create_type, request);
+        let (mut res, data, cost) = match self.try_request(create_type, request) {
+            Ok(answer) => answer,
+            Err(error) => return (Err(error.into()), 0, 0),
+        };
         if res.len() != 21 || res[0] == 0 {
             if !res.is_empty() {
                 res.remove(0);
@@ -111,6 +573,26 @@ impl<D: DataReader, H: RequestHandler<D>> EvmApi<D> for EvmApiRequestor<D, H> {
         (value.value, cost)
     }
 
+    fn prefetch_bytes32(&mut self, keys: &[Bytes32]) -> u64 {
+        let mut missing: Vec<Bytes32> = vec![];
+        for key in keys {
+            if !self.storage_cache.contains_key(key) && !missing.contains(key) {
+                missing.push(*key);
+            }
+        }
+        if missing.is_empty() {
+            return 0;
+        }
+
+        let request: Vec<u8> = missing.iter().flat_map(|key| key.0).collect();
+        let (res, _, gas) = self.request(EvmApiMethod::PrefetchBytes32, request);
+        for (key, value) in missing.into_iter().zip(res.chunks_exact(32)) {
+            let value = StorageWord::known(value.try_into().unwrap());
+            self.storage_cache.insert(key, value);
+        }
+        gas.saturating_add(EVM_API_INK)
+    }
+
     fn cache_bytes32(&mut self, key: Bytes32, value: Bytes32) -> u64 {
         let cost = self.storage_cache.write_gas();
         match self.storage_cache.entry(key) {
@@ -138,15 +620,8 @@ impl<D: DataReader, H: RequestHandler<D>> EvmApi<D> for EvmApiRequestor<D, H> {
             return Ok(0); // no need to make request
         }
 
-        let (res, _, cost) = self.request(EvmApiMethod::SetTrieSlots, data);
-        let status = res
-            .first()
-            .copied()
-            .map(EvmApiStatus::from)
-            .unwrap_or(EvmApiStatus::Failure);
-        if status != EvmApiStatus::Success {
-            bail!("{:?}", status);
-        }
+        let (res, _, cost) = self.try_request(EvmApiMethod::SetTrieSlots, data)?;
+        RequestError::check_status(&res)?;
         Ok(cost)
     }
 
@@ -159,15 +634,8 @@ impl<D: DataReader, H: RequestHandler<D>> EvmApi<D> for EvmApiRequestor<D, H> {
         let mut data = Vec::with_capacity(64);
         data.extend(key);
         data.extend(value);
-        let (res, ..) = self.request(EvmApiMethod::SetTransientBytes32, data);
-        let status = res
-            .first()
-            .copied()
-            .map(EvmApiStatus::from)
-            .unwrap_or(EvmApiStatus::Failure);
-        if status != EvmApiStatus::Success {
-            bail!("{:?}", status);
-        }
+        let (res, ..) = self.try_request(EvmApiMethod::SetTransientBytes32, data)?;
+        RequestError::check_status(&res)?;
         Ok(())
     }
 
@@ -252,7 +720,7 @@ impl<D: DataReader, H: RequestHandler<D>> EvmApi<D> for EvmApiRequestor<D, H> {
         request.extend(topics.to_be_bytes());
         request.extend(data);
 
-        let (res, _, _) = self.request(EvmApiMethod::EmitLog, request);
+        let (res, _, _) = self.try_request(EvmApiMethod::EmitLog, request)?;
         if !res.is_empty() {
             bail!(String::from_utf8(res).unwrap_or("malformed emit-log response".into()))
         }
@@ -264,6 +732,18 @@ impl<D: DataReader, H: RequestHandler<D>> EvmApi<D> for EvmApiRequestor<D, H> {
         (res.try_into().unwrap(), cost)
     }
 
+    fn self_destruct(&mut self, beneficiary: Bytes20) -> Result<u64> {
+        let (res, _, cost) = self.try_request(EvmApiMethod::SelfDestruct, beneficiary)?;
+        RequestError::check_status(&res)?;
+        Ok(cost)
+    }
+
+    fn flush_output(&mut self, data: &[u8]) -> Result<()> {
+        let (res, ..) = self.try_request(EvmApiMethod::FlushOutput, data)?;
+        RequestError::check_status(&res)?;
+        Ok(())
+    }
+
     fn account_code(&mut self, address: Bytes20, gas_left: u64) -> (D, u64) {
         if let Some((stored_address, data)) = self.last_code.as_ref() {
             if address == *stored_address {
@@ -284,6 +764,16 @@ impl<D: DataReader, H: RequestHandler<D>> EvmApi<D> for EvmApiRequestor<D, H> {
         (res.try_into().unwrap(), cost)
     }
 
+    fn account_code_size(&mut self, address: Bytes20) -> (u32, u64) {
+        let (res, _, cost) = self.request(EvmApiMethod::AccountCodeSize, address);
+        (u32::from_be_bytes(res[..4].try_into().unwrap()), cost)
+    }
+
+    fn block_hash(&mut self, number: u64) -> (Bytes32, u64) {
+        let (res, _, cost) = self.request(EvmApiMethod::BlockHash, number.to_be_bytes());
+        (res.try_into().unwrap(), cost)
+    }
+
     fn add_pages(&mut self, pages: u16) -> u64 {
         self.request(EvmApiMethod::AddPages, pages.to_be_bytes()).2
     }
@@ -308,3 +798,465 @@ impl<D: DataReader, H: RequestHandler<D>> EvmApi<D> for EvmApiRequestor<D, H> {
         self.request(EvmApiMethod::CaptureHostIO, request);
     }
 }
+
+#[cfg(test)]
+use testing::TestHandler;
+
+#[test]
+fn test_gas_reserve() {
+    assert_eq!(RequestError::check_gas_reserve(0, 0), Ok(()));
+    assert_eq!(RequestError::check_gas_reserve(100, 100), Ok(()));
+    assert_eq!(
+        RequestError::check_gas_reserve(99, 100),
+        Err(RequestError::BelowGasReserve {
+            gas_left: 99,
+            reserve: 100
+        })
+    );
+}
+
+#[test]
+fn test_flush() {
+    let mut handler = TestHandler::new(|_, _| (vec![], 0));
+    handler.buffering = true;
+    let mut api = EvmApiRequestor::new(handler);
+    api.request(EvmApiMethod::EmitLog, [0; 4]);
+    api.request(EvmApiMethod::EmitLog, [1; 4]);
+    assert_eq!(api.handler().queue.len(), 2);
+    assert!(api.handler().requests.is_empty());
+
+    api.flush().unwrap();
+    assert!(api.handler().queue.is_empty());
+    assert_eq!(api.handler().requests.len(), 2);
+}
+
+#[test]
+fn test_deadline() {
+    let deadline = 100;
+    let mut gas_used = 0;
+    for cost in [40, 40, 20] {
+        assert_eq!(RequestError::check_deadline(gas_used, deadline), Ok(()));
+        gas_used += cost;
+    }
+    assert_eq!(RequestError::check_deadline(gas_used, deadline), Ok(()));
+    assert_eq!(
+        RequestError::check_deadline(gas_used + 1, deadline),
+        Err(RequestError::DeadlineExceeded {
+            gas_used: 101,
+            deadline
+        })
+    );
+    assert_eq!(RequestError::check_deadline(u64::MAX, 0), Ok(()));
+}
+
+#[test]
+fn test_retries() {
+    let flaky = |failures: Vec<RequestError>, retries| {
+        let mut handler = TestHandler::new(|_, data| (data.to_vec(), 0));
+        handler.failures = failures;
+        RetryingRequestHandler::new(handler, retries, Duration::ZERO)
+    };
+    let unavailable = RequestError::Unavailable;
+    let deadline = RequestError::DeadlineExceeded {
+        gas_used: 2,
+        deadline: 1,
+    };
+
+    // fails twice, then succeeds
+    let mut handler = flaky(vec![unavailable, unavailable], 3);
+    let (res, ..) = handler.request(EvmApiMethod::GetBytes32, [7; 32]);
+    assert_eq!(res, [7; 32]);
+    assert_eq!(handler.inner().attempts, 3);
+
+    // runs out of retries
+    let mut handler = flaky(vec![unavailable, unavailable], 1);
+    let result = handler.try_request(EvmApiMethod::GetBytes32, [7; 32]);
+    assert_eq!(result.err(), Some(unavailable));
+    assert_eq!(handler.inner().attempts, 2);
+
+    // non-retryable errors propagate immediately
+    let mut handler = flaky(vec![deadline], 3);
+    let result = handler.try_request(EvmApiMethod::GetBytes32, [7; 32]);
+    assert_eq!(result.err(), Some(deadline));
+    assert_eq!(handler.inner().attempts, 1);
+}
+
+#[test]
+fn test_retries_exhausted() {
+    let mut handler = TestHandler::new(|method, _| match method {
+        EvmApiMethod::EmitLog => (vec![], 0),
+        _ => (vec![EvmApiStatus::Success.into()], 0),
+    });
+    handler.failures = vec![RequestError::Unavailable; 2];
+    let handler = RetryingRequestHandler::new(handler, 1, Duration::ZERO);
+    let mut api = EvmApiRequestor::new(handler);
+
+    // methods that can fail do so on the spot
+    let error = api.self_destruct(Bytes20([0xbe; 20])).unwrap_err();
+    let error = error.downcast::<RequestError>().unwrap();
+    assert_eq!(error, RequestError::Unavailable);
+    assert_eq!(api.request_handler().inner().attempts, 2);
+    assert!(api.request_handler().inner().requests.is_empty());
+
+    // later requests go through once the host recovers
+    api.emit_log(vec![], 0).unwrap();
+    assert_eq!(api.request_handler().inner().requests.len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "AccountBalance request failed: host temporarily unavailable")]
+fn test_retries_exhausted_trap() {
+    let mut handler = TestHandler::new(|_, _| (vec![0; 32], 0));
+    handler.failures = vec![RequestError::Unavailable; 2];
+    let handler = RetryingRequestHandler::new(handler, 1, Duration::ZERO);
+    let mut api = EvmApiRequestor::new(handler);
+    api.account_balance(Bytes20([0xbe; 20]));
+}
+
+#[test]
+fn test_prefetch() {
+    let keys = [Bytes32([1; 32]), Bytes32([2; 32]), Bytes32([3; 32])];
+    let handler = TestHandler::new(|_, keys| {
+        let values = keys.iter().map(|x| !x).collect();
+        let cold = 2100 * (keys.len() as u64 / 32);
+        (values, cold)
+    });
+    let mut api = EvmApiRequestor::new(handler);
+    let cost = api.prefetch_bytes32(&[keys[0], keys[1], keys[2], keys[0]]);
+    assert_eq!(cost, 3 * 2100 + EVM_API_INK);
+
+    // subsequent reads are warm and served from the cache
+    for key in keys {
+        let (value, cost) = api.get_bytes32(key);
+        assert_eq!(value, Bytes32(key.0.map(|x| !x)));
+        assert_eq!(cost, 0);
+    }
+    assert_eq!(api.prefetch_bytes32(&keys), 0);
+
+    let requests = &api.handler().requests;
+    assert!(matches!(requests[..], [(EvmApiMethod::PrefetchBytes32, _)]));
+}
+
+#[test]
+fn test_request_errors() {
+    let errors = [
+        RequestError::check_id(1, 2).unwrap_err(),
+        RequestError::check_payload_len(33, 32).unwrap_err(),
+        RequestError::check_status(&[EvmApiStatus::WriteProtection.into()]).unwrap_err(),
+        RequestError::check_status(&[]).unwrap_err(),
+        RequestError::from(MemoryBoundsError),
+    ];
+    for error in errors {
+        let report = eyre::ErrReport::from(error);
+        assert_eq!(report.downcast_ref::<RequestError>(), Some(&error));
+
+        match error {
+            RequestError::IdMismatch { expected, actual } => {
+                assert_eq!((expected, actual), (1, 2));
+            }
+            RequestError::OversizedPayload { len, limit } => {
+                assert_eq!((len, limit), (33, 32));
+            }
+            RequestError::Host(status) => {
+                let statuses = [EvmApiStatus::WriteProtection, EvmApiStatus::Failure];
+                assert!(statuses.contains(&status));
+            }
+            RequestError::Memory(_) => {
+                assert_eq!(report.to_string(), "memory access out of bounds");
+            }
+            _ => unreachable!("unexpected error {error}"),
+        }
+    }
+    assert_eq!(RequestError::check_id(7, 7), Ok(()));
+    assert_eq!(RequestError::check_payload_len(usize::MAX, 0), Ok(()));
+    assert_eq!(RequestError::check_status(&[0]), Ok(()));
+}
+
+#[test]
+fn test_self_destruct() {
+    let beneficiary = Bytes20([0xbe; 20]);
+    let handler = TestHandler::new(|_, _| (vec![EvmApiStatus::Success.into()], 2600));
+    let mut api = EvmApiRequestor::new(handler);
+    assert_eq!(api.self_destruct(beneficiary).unwrap(), 2600);
+
+    let requests = &api.handler().requests;
+    assert_eq!(requests.len(), 1);
+    assert!(matches!(requests[0].0, EvmApiMethod::SelfDestruct));
+    assert_eq!(requests[0].1, beneficiary.0);
+}
+
+#[test]
+fn test_request_budget() {
+    let budget = 100;
+    let mut in_flight = vec![];
+    for len in [40, 40] {
+        let total = in_flight.iter().sum();
+        assert_eq!(
+            RequestError::check_request_budget(total, len, budget),
+            Ok(())
+        );
+        in_flight.push(len);
+    }
+    assert_eq!(
+        RequestError::check_request_budget(80, 40, budget),
+        Err(RequestError::RequestBudgetExceeded {
+            in_flight: 80,
+            len: 40,
+            budget
+        })
+    );
+
+    // answering a request frees its share of the budget
+    in_flight.pop();
+    let total = in_flight.iter().sum();
+    assert_eq!(
+        RequestError::check_request_budget(total, 40, budget),
+        Ok(())
+    );
+    assert_eq!(RequestError::check_request_budget(usize::MAX, 1, 0), Ok(()));
+}
+
+#[test]
+fn test_dry_run() {
+    let mut api = EvmApiRequestor::new(DryRunRequestHandler::new(100));
+    let key = Bytes32([1; 32]);
+    let address = Bytes20([2; 20]);
+
+    assert_eq!(api.get_bytes32(key).0, Bytes32::default());
+    assert_eq!(api.account_balance(address), (Bytes32::default(), 100));
+    assert_eq!(api.self_destruct(address).unwrap(), 100);
+    api.get_bytes32(key); // cached, so not requested
+
+    let requests = api.handler().requests();
+    let methods: Vec<_> = requests.iter().map(|(method, _)| *method as u32).collect();
+    let expected = [
+        EvmApiMethod::GetBytes32,
+        EvmApiMethod::AccountBalance,
+        EvmApiMethod::SelfDestruct,
+    ];
+    assert_eq!(methods, expected.map(|method| method as u32));
+    assert_eq!(requests[0].1, key.0);
+    assert_eq!(requests[1].1, address.0);
+
+    // methods are answered with the configured default instead
+    let balance = Bytes32([3; 32]);
+    let mut handler = DryRunRequestHandler::new(100);
+    handler.set_default_answer(balance.to_vec());
+    let mut api = EvmApiRequestor::new(handler);
+    assert_eq!(api.account_balance(address), (balance, 100));
+}
+
+#[test]
+fn test_request_count() {
+    let limit = 3;
+    for made in 0..limit {
+        assert_eq!(RequestError::check_request_count(made, limit), Ok(()));
+    }
+    assert_eq!(
+        RequestError::check_request_count(limit, limit),
+        Err(RequestError::RequestLimitExceeded { limit })
+    );
+    assert_eq!(RequestError::check_request_count(u32::MAX, 0), Ok(()));
+}
+
+#[test]
+fn test_timeout() {
+    let start = Instant::now();
+    let deadline = start + Duration::from_millis(10);
+    assert_eq!(RequestError::check_timeout(start, Some(deadline)), Ok(()));
+    assert_eq!(RequestError::check_timeout(start, None), Ok(()));
+
+    // a slow host bridge, with the clock passed in rather than waited on
+    let now = start + Duration::from_millis(20);
+    assert_eq!(
+        RequestError::check_timeout(now, Some(deadline)),
+        Err(RequestError::Timeout)
+    );
+    assert_eq!(RequestError::check_timeout(now, None), Ok(()));
+
+    // the deadline itself is already too late
+    assert_eq!(
+        RequestError::check_timeout(deadline, Some(deadline)),
+        Err(RequestError::Timeout)
+    );
+}
+
+#[test]
+fn test_gas_cost() {
+    assert_eq!(
+        RequestError::check_gas_cost(OUT_OF_GAS),
+        Err(RequestError::OutOfGas)
+    );
+    assert_eq!(RequestError::check_gas_cost(0), Ok(0));
+    assert_eq!(
+        RequestError::check_gas_cost(OUT_OF_GAS - 1),
+        Ok(OUT_OF_GAS - 1)
+    );
+}
+
+#[test]
+fn test_method_timings() {
+    // the durations a host bridge would have measured
+    let mut timings = MethodTimings::default();
+    timings.record(EvmApiMethod::GetBytes32, Duration::from_millis(10));
+    timings.record(EvmApiMethod::AccountBalance, Duration::from_millis(5));
+    timings.record(EvmApiMethod::GetBytes32, Duration::from_millis(15));
+
+    let time = |method| timings.get(method).as_millis();
+    assert_eq!(time(EvmApiMethod::GetBytes32), 25);
+    assert_eq!(time(EvmApiMethod::AccountBalance), 5);
+    assert_eq!(time(EvmApiMethod::EmitLog), 0);
+    assert_eq!(timings.iter().count(), 2);
+}
+
+#[test]
+fn test_request_stats() {
+    let mut stats = RequestStats::default();
+    stats.record(EvmApiMethod::GetBytes32);
+    stats.record(EvmApiMethod::EmitLog);
+    stats.record(EvmApiMethod::GetBytes32);
+    assert_eq!(stats.get(EvmApiMethod::GetBytes32), 2);
+    assert_eq!(stats.get(EvmApiMethod::EmitLog), 1);
+    assert_eq!(stats.get(EvmApiMethod::AccountCode), 0);
+    assert_eq!(stats.total(), 3);
+    assert_eq!(stats.iter().count(), 2);
+}
+
+#[test]
+fn test_storage_cache_fork() {
+    let mut api = EvmApiRequestor::new(DryRunRequestHandler::new(0));
+    let (key, other) = (Bytes32([1; 32]), Bytes32([2; 32]));
+    api.cache_bytes32(key, Bytes32([3; 32]));
+
+    let fork = api.storage_cache().clone();
+    api.cache_bytes32(key, Bytes32([4; 32]));
+    api.cache_bytes32(other, Bytes32([5; 32]));
+
+    // the fork saw neither write
+    assert_eq!(fork.len(), 1);
+    assert_eq!(fork[&key].value, Bytes32([3; 32]));
+    assert_eq!(api.storage_cache()[&key].value, Bytes32([4; 32]));
+    assert_eq!(api.storage_cache().len(), 2);
+}
+
+#[test]
+fn test_response_len() {
+    assert_eq!(RequestError::check_response_len(64, 64), Ok(()));
+    assert_eq!(RequestError::check_response_len(usize::MAX, 0), Ok(()));
+    assert_eq!(
+        RequestError::check_response_len(65, 64),
+        Err(RequestError::OversizedResponse { len: 65, limit: 64 })
+    );
+}
+
+#[test]
+fn test_dirty_slots() {
+    let mut api = EvmApiRequestor::new(DryRunRequestHandler::new(0));
+    let [a, b, c] = [1_u32, 2, 3].map(Bytes32::from);
+    api.cache_bytes32(b, Bytes32([5; 32]));
+    api.cache_bytes32(a, Bytes32([4; 32]));
+    api.get_bytes32(c);
+
+    let dirty = api.storage_cache().dirty_slots();
+    assert_eq!(dirty, vec![(a, Bytes32([4; 32])), (b, Bytes32([5; 32]))]);
+
+    // flushing makes every slot clean again
+    api.flush_storage_cache(false, 0).unwrap();
+    assert_eq!(api.storage_cache().len(), 3);
+    assert!(api.storage_cache().dirty_slots().is_empty());
+}
+
+#[test]
+fn test_chaos() {
+    let chaos = |seed| ChaosRequestHandler::new(DryRunRequestHandler::new(0), seed);
+
+    // a truncated status byte is taken as a failure
+    let mut handler = chaos(0);
+    handler.add_fault(Fault::Truncate(0), 1);
+    let mut api = EvmApiRequestor::new(handler);
+    api.cache_bytes32(Bytes32([1; 32]), Bytes32([2; 32]));
+    let error = api.flush_storage_cache(false, 0).unwrap_err();
+    let error = error.downcast::<RequestError>().unwrap();
+    assert_eq!(error, RequestError::Host(EvmApiStatus::Failure));
+
+    // the same seed corrupts the same bits
+    let corrupted = |seed| {
+        let mut handler = chaos(seed);
+        handler.add_fault(Fault::Corrupt, 2);
+        let answers = (0..16).map(|_| handler.request(EvmApiMethod::GetBytes32, []).0);
+        answers.collect::<Vec<_>>()
+    };
+    let answers = corrupted(7);
+    assert_eq!(answers, corrupted(7));
+    assert!(answers.iter().any(|x| x != &[0; 32]));
+    assert!(answers.iter().any(|x| x == &[0; 32]));
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    /// A stand-in for the host, configured per test.
+    pub(super) struct TestHandler {
+        /// Answers each request given its method and payload, as `(result, gas)`.
+        answer: fn(EvmApiMethod, &[u8]) -> (Vec<u8>, u64),
+        /// Errors to fail with, from last to first, before answering anything.
+        pub failures: Vec<RequestError>,
+        /// Whether requests are queued until flushed rather than completed immediately.
+        pub buffering: bool,
+        /// Requests awaiting a flush.
+        pub queue: Vec<(EvmApiMethod, Vec<u8>)>,
+        /// Every request completed, in order.
+        pub requests: Vec<(EvmApiMethod, Vec<u8>)>,
+        /// The number of requests attempted, including those that failed.
+        pub attempts: usize,
+    }
+
+    impl TestHandler {
+        pub fn new(answer: fn(EvmApiMethod, &[u8]) -> (Vec<u8>, u64)) -> Self {
+            Self {
+                answer,
+                failures: vec![],
+                buffering: false,
+                queue: vec![],
+                requests: vec![],
+                attempts: 0,
+            }
+        }
+    }
+
+    impl RequestHandler<VecReader> for TestHandler {
+        fn request(
+            &mut self,
+            req_type: EvmApiMethod,
+            req_data: impl AsRef<[u8]>,
+        ) -> (Vec<u8>, VecReader, u64) {
+            self.try_request(req_type, req_data).unwrap()
+        }
+
+        fn try_request(
+            &mut self,
+            req_type: EvmApiMethod,
+            req_data: impl AsRef<[u8]>,
+        ) -> Result<(Vec<u8>, VecReader, u64), RequestError> {
+            self.attempts += 1;
+            if let Some(error) = self.failures.pop() {
+                return Err(error);
+            }
+            let req_data = req_data.as_ref();
+            let (res, gas) = (self.answer)(req_type, req_data);
+            let request = (req_type, req_data.to_vec());
+            if self.buffering {
+                self.queue.push(request);
+            } else {
+                self.requests.push(request);
+            }
+            Ok((res, VecReader::default(), gas))
+        }
+
+        fn flush(&mut self) -> Result<(), RequestError> {
+            self.requests.append(&mut self.queue);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,174 @@
+// Copyright 2024, Offchain Labs, Inc.
+// For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+//! Recursive Length Prefix encoding, as used by Ethereum's transactions and tries.
+
+use eyre::{bail, eyre, Result};
+
+/// A value in RLP's data model: a byte string or a list of values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RlpItem {
+    Bytes(Vec<u8>),
+    List(Vec<RlpItem>),
+}
+
+/// Encodes each item in turn, concatenating the results.
+pub fn encode(items: &[RlpItem]) -> Vec<u8> {
+    let mut data = vec![];
+    for item in items {
+        encode_item(&mut data, item);
+    }
+    data
+}
+
+fn encode_item(data: &mut Vec<u8>, item: &RlpItem) {
+    match item {
+        RlpItem::Bytes(bytes) => {
+            if let [byte @ 0..=0x7f] = bytes.as_slice() {
+                data.push(*byte);
+                return;
+            }
+            put_prefix(data, 0x80, bytes.len());
+            data.extend(bytes);
+        }
+        RlpItem::List(items) => {
+            let payload = encode(items);
+            put_prefix(data, 0xc0, payload.len());
+            data.extend(payload);
+        }
+    }
+}
+
+/// Writes the prefix of a payload of `len` bytes, whose short form begins at `offset`.
+fn put_prefix(data: &mut Vec<u8>, offset: u8, len: usize) {
+    if len <= 55 {
+        data.push(offset + len as u8);
+        return;
+    }
+    let len = (len as u64).to_be_bytes();
+    let skip = len.iter().take_while(|&&x| x == 0).count();
+    data.push(offset + 55 + (len.len() - skip) as u8);
+    data.extend(&len[skip..]);
+}
+
+/// Decodes a concatenation of items, rejecting any encoding but the canonical one.
+pub fn decode(mut data: &[u8]) -> Result<Vec<RlpItem>> {
+    let mut items = vec![];
+    while !data.is_empty() {
+        let (item, rest) = decode_item(data)?;
+        items.push(item);
+        data = rest;
+    }
+    Ok(items)
+}
+
+fn decode_item(data: &[u8]) -> Result<(RlpItem, &[u8])> {
+    let (&prefix, data) = data.split_first().ok_or_else(|| eyre!("missing prefix"))?;
+    if prefix < 0x80 {
+        return Ok((RlpItem::Bytes(vec![prefix]), data));
+    }
+    let offset = if prefix < 0xc0 { 0x80 } else { 0xc0 };
+    let (len, data) = read_len(prefix - offset, data)?;
+    if data.len() < len {
+        bail!("payload of {len} bytes truncated to {}", data.len());
+    }
+    let (payload, rest) = data.split_at(len);
+    if offset == 0xc0 {
+        return Ok((RlpItem::List(decode(payload)?), rest));
+    }
+    if let [byte @ 0..=0x7f] = payload {
+        bail!("byte {byte:#04x} needlessly prefixed");
+    }
+    Ok((RlpItem::Bytes(payload.to_vec()), rest))
+}
+
+/// Reads the length of a payload, given how far its prefix is from the start of its range.
+fn read_len(short: u8, data: &[u8]) -> Result<(usize, &[u8])> {
+    if short <= 55 {
+        return Ok((short as usize, data));
+    }
+    let size = (short - 55) as usize;
+    if data.len() < size {
+        bail!("length of {size} bytes truncated to {}", data.len());
+    }
+    let (len, data) = data.split_at(size);
+    if len[0] == 0 {
+        bail!("length has leading zeros");
+    }
+    let mut word = [0; 8];
+    word[8 - size..].copy_from_slice(len);
+    let len = u64::from_be_bytes(word);
+    if len <= 55 {
+        bail!("length {len} should use the short form");
+    }
+    let len = usize::try_from(len).map_err(|_| eyre!("length {len} is too large"))?;
+    Ok((len, data))
+}
+
+#[cfg(test)]
+fn bytes(data: &[u8]) -> RlpItem {
+    RlpItem::Bytes(data.to_vec())
+}
+
+#[test]
+fn test_encode() {
+    let list = RlpItem::List;
+    let encoded = |items: &[RlpItem]| hex::encode(encode(items));
+
+    // strings, from the Ethereum yellow paper and wiki
+    assert_eq!(encoded(&[bytes(b"dog")]), "83646f67");
+    assert_eq!(encoded(&[bytes(b"")]), "80");
+    assert_eq!(encoded(&[bytes(&[0])]), "00");
+    assert_eq!(encoded(&[bytes(&[0x0f])]), "0f");
+    assert_eq!(encoded(&[bytes(&[0x80])]), "8180");
+    assert_eq!(encoded(&[bytes(&[0x04, 0x00])]), "820400");
+
+    let lorem = b"Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+    let expected = format!("b838{}", hex::encode(lorem));
+    assert_eq!(encoded(&[bytes(lorem)]), expected);
+
+    // lists, including the empty list and the set-theoretic representation of three
+    let dogs = list(vec![bytes(b"cat"), bytes(b"dog")]);
+    assert_eq!(encoded(&[dogs]), "c88363617483646f67");
+    assert_eq!(encoded(&[list(vec![])]), "c0");
+
+    let zero = || list(vec![]);
+    let one = || list(vec![zero()]);
+    let two = list(vec![zero(), one()]);
+    let three = list(vec![zero(), one(), two]);
+    assert_eq!(encoded(&[three]), "c7c0c1c0c3c0c1c0");
+
+    // a list whose payload needs the long form
+    let long = list(vec![bytes(lorem)]);
+    assert_eq!(encoded(&[long]), format!("f83a{expected}"));
+
+    // several items are concatenated
+    assert_eq!(encoded(&[bytes(b"dog"), bytes(b"")]), "83646f6780");
+    assert_eq!(encoded(&[]), "");
+}
+
+#[test]
+fn test_decode() {
+    let lorem = bytes(b"Lorem ipsum dolor sit amet, consectetur adipisicing elit");
+    let list = RlpItem::List;
+    let items = vec![
+        bytes(b""),
+        bytes(&[0]),
+        bytes(&[0x80]),
+        bytes(b"dog"),
+        lorem.clone(),
+        list(vec![]),
+        list(vec![bytes(b"cat"), list(vec![lorem, list(vec![])])]),
+    ];
+    assert_eq!(decode(&encode(&items)).unwrap(), items);
+    assert_eq!(decode(&[]).unwrap(), vec![]);
+
+    // malformed and non-canonical encodings
+    let fails = |data: &str| decode(&hex::decode(data).unwrap()).is_err();
+    assert!(fails("83646f")); // truncated payload
+    assert!(fails("b9")); // truncated length
+    assert!(fails("8100")); // single byte with a prefix
+    assert!(fails("b803646f67")); // long form for a short payload
+    assert!(fails("b9003a")); // length with leading zeros
+    assert!(fails("c283646f67")); // list payload ends partway through an item
+}
@@ -6,7 +6,7 @@ use fnv::FnvHashMap as HashMap;
 use std::ops::{Deref, DerefMut};
 
 /// Represents the EVM word at a given key.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct StorageWord {
     /// The current value of the slot.
     pub value: Bytes32,
@@ -29,7 +29,7 @@ impl StorageWord {
     }
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct StorageCache {
     pub(crate) slots: HashMap<Bytes32, StorageWord>,
     reads: usize,
@@ -56,6 +56,14 @@ impl StorageCache {
             _ => 10,
         }
     }
+
+    /// The key and value of each slot written but not yet flushed to Geth, sorted by key.
+    pub fn dirty_slots(&self) -> Vec<(Bytes32, Bytes32)> {
+        let dirty = self.slots.iter().filter(|(_, word)| word.dirty());
+        let mut slots: Vec<_> = dirty.map(|(key, word)| (*key, word.value)).collect();
+        slots.sort_unstable();
+        slots
+    }
 }
 
 impl Deref for StorageCache {
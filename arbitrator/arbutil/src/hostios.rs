@@ -17,8 +17,21 @@ impl Display for ParamType {
 
 use ParamType::*;
 
+/// The first Stylus version whose programs may import each hostio added since launch.
+/// Importing one any earlier is rejected at activation and when building the module.
+pub const HOSTIO_VERSIONS: [(&str, u16); 3] =
+    [("mcopy", 3), ("selfdestruct", 3), ("flush_output", 3)];
+
+/// Whether programs of the given Stylus version may import the named hostio.
+pub fn hostio_available(name: &str, version: u16) -> bool {
+    match HOSTIO_VERSIONS.iter().find(|(hostio, _)| *hostio == name) {
+        Some(&(_, first)) => version >= first,
+        None => true,
+    }
+}
+
 /// order matters!
-pub const HOSTIOS: [(&str, &[ParamType], &[ParamType]); 42] = [
+pub const HOSTIOS: [(&str, &[ParamType], &[ParamType]); 45] = [
     ("read_args", &[I32], &[]),
     ("write_result", &[I32, I32], &[]),
     ("exit_early", &[I32], &[]),
@@ -61,4 +74,21 @@ pub const HOSTIOS: [(&str, &[ParamType], &[ParamType]); 42] = [
     ("tx_ink_price", &[], &[I32]),
     ("tx_origin", &[I32], &[]),
     ("pay_for_memory_grow", &[I32], &[]),
+    ("mcopy", &[I32, I32, I32], &[]),
+    ("selfdestruct", &[I32], &[]),
+    ("flush_output", &[], &[]),
 ];
+
+#[test]
+fn test_hostio_versions() {
+    assert!(hostio_available("read_args", 1));
+    assert!(!hostio_available("mcopy", 2));
+    assert!(hostio_available("mcopy", 3));
+    assert!(!hostio_available("selfdestruct", 2));
+    assert!(!hostio_available("flush_output", 2));
+
+    // every gated hostio must exist
+    for (name, _) in HOSTIO_VERSIONS {
+        assert!(HOSTIOS.iter().any(|(hostio, ..)| *hostio == name));
+    }
+}
@@ -14,6 +14,7 @@ pub mod types;
 
 pub use color::{Color, DebugColor};
 use num_traits::Unsigned;
+use std::{borrow::Cow, fmt::Display};
 pub use types::{Bytes20, Bytes32, PreimageType};
 
 /// Puts an arbitrary type on the heap.
@@ -37,6 +38,45 @@ where
     &data[start..end.min(data.len())]
 }
 
+/// Clamps an access of `len` bytes at `ptr` to a memory of `size` bytes, returning the number of
+/// bytes available. Returns `None` only when `ptr` itself lies beyond the end of memory.
+pub fn clamp_access(ptr: u64, len: u64, size: u64) -> Option<u64> {
+    let available = size.checked_sub(ptr)?;
+    Some(len.min(available))
+}
+
+/// Marks traced hostio data cut short by [`truncate_trace_data`].
+pub const TRUNCATED_MARKER: &[u8] = b"[truncated]";
+
+/// Shortens traced hostio data to `limit` bytes followed by the [`TRUNCATED_MARKER`], copying
+/// rather than modifying the data the hostio sees. A limit of `0` never truncates.
+pub fn truncate_trace_data(data: &[u8], limit: u32) -> Cow<'_, [u8]> {
+    let limit = limit as usize;
+    if limit == 0 || data.len() <= limit {
+        return Cow::Borrowed(data);
+    }
+    Cow::Owned([&data[..limit], TRUNCATED_MARKER].concat())
+}
+
+/// Signifies an out-of-bounds memory access was requested.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryBoundsError;
+
+impl std::error::Error for MemoryBoundsError {}
+
+impl Display for MemoryBoundsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "memory access out of bounds")
+    }
+}
+
+impl MemoryBoundsError {
+    /// Encodes the fault as an `Error(string)` revert, for when it should surface to the caller.
+    pub fn to_revert_bytes(&self) -> Vec<u8> {
+        evm::abi::encode_revert_reason(&self.to_string())
+    }
+}
+
 #[test]
 fn test_limit_vec() {
     let testvec = vec![0, 1, 2, 3];
@@ -49,3 +89,38 @@ fn test_limit_vec() {
     assert_eq!(slice_with_runoff(&testvec, 0_u8, 5), &testvec[0..4]);
     assert_eq!(slice_with_runoff(&testvec, 2, usize::MAX), &testvec[2..4]);
 }
+
+#[test]
+fn test_clamp_access() {
+    assert_eq!(clamp_access(0, 32, 64), Some(32));
+    assert_eq!(clamp_access(32, 32, 64), Some(32));
+    assert_eq!(clamp_access(48, 32, 64), Some(16));
+    assert_eq!(clamp_access(64, 32, 64), Some(0));
+    assert_eq!(clamp_access(65, 32, 64), None);
+    assert_eq!(clamp_access(u64::MAX, u64::MAX, 64), None);
+}
+
+#[test]
+fn test_revert_bytes() {
+    let data = MemoryBoundsError.to_revert_bytes();
+    let mut reader = evm::abi::CalldataReader::new(&data);
+    assert_eq!(reader.selector().unwrap(), evm::abi::ERROR_SELECTOR);
+    assert_eq!(reader.read_bytes().unwrap(), b"memory access out of bounds");
+    assert_eq!(data.len(), 4 + 32 * 3);
+}
+
+#[test]
+fn test_truncate_trace_data() {
+    let args = vec![7; 1024];
+    let traced = truncate_trace_data(&args, 16);
+    assert_eq!(traced.len(), 16 + TRUNCATED_MARKER.len());
+    assert_eq!(&traced[..16], &args[..16]);
+    assert!(traced.ends_with(TRUNCATED_MARKER));
+
+    // the hostio's own data is left whole
+    assert_eq!(args, vec![7; 1024]);
+
+    // short data, or no limit at all
+    assert!(matches!(truncate_trace_data(&args, 1024), Cow::Borrowed(_)));
+    assert!(matches!(truncate_trace_data(&args, 0), Cow::Borrowed(_)));
+}
@@ -1,6 +1,7 @@
 // Copyright 2023, Offchain Labs, Inc.
 // For license information, see https://github.com/nitro/blob/master/LICENSE
 
+use num_bigint::BigUint;
 use num_traits::{ops::saturating::SaturatingAdd, Zero};
 use std::ops::{BitAnd, Sub};
 
@@ -41,3 +42,41 @@ pub fn div_ceil<const N: usize>(num: usize) -> usize {
         _ => num / N + 1,
     }
 }
+
+/// Computes `base ^ exp % modulus` over big-endian integers, as does the EVM's precompile at
+/// address `0x05`. The result is left-padded to the modulus's length, and is zero if the modulus is.
+pub fn modexp(base: &[u8], exp: &[u8], modulus: &[u8]) -> Vec<u8> {
+    let mut output = vec![0; modulus.len()];
+    let modulus = BigUint::from_bytes_be(modulus);
+    if modulus.is_zero() {
+        return output;
+    }
+    let base = BigUint::from_bytes_be(base);
+    let exp = BigUint::from_bytes_be(exp);
+    let result = base.modpow(&exp, &modulus).to_bytes_be();
+
+    let start = output.len() - result.len();
+    output[start..].copy_from_slice(&result);
+    output
+}
+
+#[test]
+fn test_modexp() {
+    // 2^10 % 1000
+    assert_eq!(modexp(&[2], &[10], &[0x03, 0xe8]), [0x00, 0x18]);
+
+    // Fermat's little theorem over the secp256k1 field, as in EIP-198
+    let p =
+        hex::decode("fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f").unwrap();
+    let exp =
+        hex::decode("fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e").unwrap();
+    let mut one = vec![0; 32];
+    one[31] = 1;
+    assert_eq!(modexp(&[3], &exp, &p), one);
+
+    // zero exponents, moduli of one, and zero moduli
+    assert_eq!(modexp(&[7], &[], &[0, 5]), [0, 1]);
+    assert_eq!(modexp(&[7], &[3], &[1]), [0]);
+    assert_eq!(modexp(&[7], &[3], &[0, 0, 0]), [0, 0, 0]);
+    assert_eq!(modexp(&[7], &[3], &[]), Vec::<u8>::new());
+}
@@ -242,6 +242,55 @@ impl fmt::Debug for Bytes20 {
     }
 }
 
+/// Reasons an address string may fail to parse. See [`Bytes20::from_checksummed`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddressParseError {
+    /// The string isn't `0x` followed by 40 hex digits.
+    Malformed,
+    /// The string is mixed-case but doesn't match its EIP-55 checksum.
+    BadChecksum,
+}
+
+impl std::error::Error for AddressParseError {}
+
+impl fmt::Display for AddressParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed => write!(f, "malformed address"),
+            Self::BadChecksum => write!(f, "address checksum mismatch"),
+        }
+    }
+}
+
+impl Bytes20 {
+    /// Parses a `0x`-prefixed hex address, verifying its [EIP-55] checksum if it's mixed-case.
+    /// An address in a single case carries no checksum and is accepted as is.
+    ///
+    /// [EIP-55]: https://eips.ethereum.org/EIPS/eip-55
+    pub fn from_checksummed(text: &str) -> Result<Self, AddressParseError> {
+        let digits = text
+            .strip_prefix("0x")
+            .ok_or(AddressParseError::Malformed)?;
+        if digits.len() != 40 || !digits.bytes().all(|x| x.is_ascii_hexdigit()) {
+            return Err(AddressParseError::Malformed);
+        }
+        let address = Self(hex::decode(digits).unwrap().try_into().unwrap());
+
+        let lower = digits.to_ascii_lowercase();
+        if digits == lower || digits == digits.to_ascii_uppercase() {
+            return Ok(address);
+        }
+        let hash = crate::crypto::keccak(lower);
+        for (i, digit) in digits.bytes().enumerate() {
+            let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0xf;
+            if digit.is_ascii_alphabetic() && digit.is_ascii_uppercase() != (nibble >= 8) {
+                return Err(AddressParseError::BadChecksum);
+            }
+        }
+        Ok(address)
+    }
+}
+
 type GenericBytes20 = digest::generic_array::GenericArray<u8, digest::generic_array::typenum::U20>;
 
 impl From<GenericBytes20> for Bytes20 {
@@ -249,3 +298,38 @@ impl From<GenericBytes20> for Bytes20 {
         <[u8; 20]>::from(x).into()
     }
 }
+
+#[test]
+fn test_checksummed_address() {
+    // from EIP-55
+    let valid = [
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ];
+    for text in valid {
+        let address = Bytes20::from_checksummed(text).unwrap();
+        assert_eq!(format!("0x{address}"), text.to_ascii_lowercase());
+    }
+
+    // no checksum to verify
+    let lower = "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed";
+    assert_eq!(
+        Bytes20::from_checksummed(lower),
+        Bytes20::from_checksummed(valid[0])
+    );
+
+    // the last letter's case flipped
+    let flipped = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD";
+    let bad_checksum = Err(AddressParseError::BadChecksum);
+    assert_eq!(Bytes20::from_checksummed(flipped), bad_checksum);
+
+    let malformed = Err(AddressParseError::Malformed);
+    assert_eq!(Bytes20::from_checksummed(&lower[2..]), malformed);
+    assert_eq!(Bytes20::from_checksummed(&lower[..41]), malformed);
+    assert_eq!(
+        Bytes20::from_checksummed("0xzaaeb6053f3e94c9b9a09f33669435e7ef1beaed"),
+        malformed
+    );
+}
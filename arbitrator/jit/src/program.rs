@@ -11,10 +11,7 @@ use arbutil::{evm::EvmData, format::DebugBytes, heapify};
 use caller_env::{GuestPtr, MemAccess};
 use eyre::eyre;
 use prover::programs::prelude::StylusConfig;
-use prover::{
-    machine::Module,
-    programs::{config::PricingParams, prelude::*},
-};
+use prover::{machine::Module, programs::prelude::*};
 
 /// activates a user program
 pub fn activate(
@@ -212,11 +209,7 @@ pub fn create_stylus_config(
     ink_price: u32,
     debug: u32,
 ) -> Result<u64, Escape> {
-    let stylus = StylusConfig {
-        version,
-        max_depth,
-        pricing: PricingParams { ink_price },
-    };
+    let stylus = StylusConfig::new(version, max_depth, ink_price);
     let compile = CompileConfig::version(version, debug != 0);
     let res = heapify(JitConfig { stylus, compile });
     Ok(res as u64)
@@ -231,6 +224,7 @@ pub fn create_evm_data(
     block_gas_limit: u64,
     block_number: u64,
     block_timestamp: u64,
+    block_randomness_ptr: GuestPtr,
     contract_address_ptr: GuestPtr,
     module_hash_ptr: GuestPtr,
     msg_sender_ptr: GuestPtr,
@@ -239,6 +233,7 @@ pub fn create_evm_data(
     tx_origin_ptr: GuestPtr,
     cached: u32,
     reentrant: u32,
+    read_only: u32,
 ) -> Result<u64, Escape> {
     let (mut mem, _) = env.jit_env();
 
@@ -250,6 +245,7 @@ pub fn create_evm_data(
         block_gas_limit,
         block_number,
         block_timestamp,
+        block_randomness: mem.read_bytes32(block_randomness_ptr),
         contract_address: mem.read_bytes20(contract_address_ptr),
         module_hash: mem.read_bytes32(module_hash_ptr),
         msg_sender: mem.read_bytes20(msg_sender_ptr),
@@ -259,6 +255,7 @@ pub fn create_evm_data(
         reentrant,
         return_data_len: 0,
         tracing: false,
+        read_only: read_only != 0,
     };
     let res = heapify(evm_data);
     Ok(res as u64)
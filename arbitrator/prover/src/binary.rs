@@ -9,7 +9,7 @@ use crate::{
     },
     value::{ArbValueType, FunctionType, IntegerValType, Value},
 };
-use arbutil::{math::SaturatingSum, Bytes32, Color, DebugColor};
+use arbutil::{hostios, math::SaturatingSum, Bytes32, Color, DebugColor};
 use eyre::{bail, ensure, eyre, Result, WrapErr};
 use fnv::{FnvHashMap as HashMap, FnvHashSet as HashSet};
 use nom::{
@@ -650,6 +650,7 @@ impl<'a> WasmBinary<'a> {
         codehash: &Bytes32,
     ) -> Result<(WasmBinary<'a>, StylusData)> {
         let mut bin = parse(wasm, Path::new("user"))?;
+        bin.check_hostio_versions(compile.version)?;
         let stylus_data = bin.instrument(compile, codehash)?;
 
         let Some(memory) = bin.memories.first() else {
@@ -711,6 +712,17 @@ impl<'a> WasmBinary<'a> {
         Ok((bin, stylus_data))
     }
 
+    /// Ensures the program imports no hostio introduced after its Stylus version.
+    pub fn check_hostio_versions(&self, version: u16) -> Result<()> {
+        for import in self.imports.iter().filter(|x| x.module == "vm_hooks") {
+            if !hostios::hostio_available(import.name, version) {
+                let name = import.name.red();
+                bail!("hostio {name} unavailable in Stylus version {version}");
+            }
+        }
+        Ok(())
+    }
+
     /// Ensures a func exists and has the right type.
     fn check_func(&self, name: &str, ty: FunctionType) -> Result<u32> {
         let Some(&(func, kind)) = self.exports.get(name) else {
@@ -98,6 +98,7 @@ pub enum Hostio {
     ConsoleTeeI64,
     ConsoleTeeF32,
     ConsoleTeeF64,
+    ConsoleDebugAssert,
     UserInkLeft,
     UserInkStatus,
     UserSetInk,
@@ -148,6 +149,7 @@ impl FromStr for Hostio {
             ("console", "tee_i64") => ConsoleTeeI64,
             ("console", "tee_f32") => ConsoleTeeF32,
             ("console", "tee_f64") => ConsoleTeeF64,
+            ("console", "debug_assert") => ConsoleDebugAssert,
             _ => bail!("no such hostio {} in {}", name.red(), module.red()),
         })
     }
@@ -206,6 +208,7 @@ impl Hostio {
             ConsoleTeeI64               => func!([I64], [I64]),      // λ(value) → value
             ConsoleTeeF32               => func!([F32], [F32]),      // λ(value) → value
             ConsoleTeeF64               => func!([F64], [F64]),      // λ(value) → value
+            ConsoleDebugAssert          => func!([I32, I32, I32]),   // λ(cond, text, len)
             UserInkLeft                 => InternalFunc::UserInkLeft.ty(),
             UserInkStatus               => InternalFunc::UserInkStatus.ty(),
             UserSetInk                  => InternalFunc::UserSetInk.ty(),
@@ -213,7 +216,7 @@ impl Hostio {
         ty
     }
 
-    pub fn body(&self, _prior: usize) -> Vec<Instruction> {
+    pub fn body(&self, prior: usize) -> Vec<Instruction> {
         let mut body = vec![];
 
         macro_rules! opcode {
@@ -400,6 +403,12 @@ impl Hostio {
             ConsoleTeeI32 | ConsoleTeeI64 | ConsoleTeeF32 | ConsoleTeeF64 => {
                 opcode!(LocalGet, 0);
             }
+            ConsoleDebugAssert => {
+                // λ(cond, text, len), trapping when cond is zero
+                opcode!(LocalGet, 0);
+                opcode!(ArbitraryJumpIf, prior + 3);
+                opcode!(Unreachable);
+            }
         }
         body
     }
@@ -652,9 +652,11 @@ impl Module {
 
     pub fn from_user_binary(
         bin: &WasmBinary,
+        version: u16,
         debug_funcs: bool,
         stylus_data: Option<StylusData>,
     ) -> Result<Module> {
+        bin.check_hostio_versions(version)?;
         Self::from_binary(
             bin,
             Module::make_imports_resolver(&USER_IMPORTS)
@@ -1352,7 +1354,7 @@ impl Machine {
             self.debug_info = true;
         }
 
-        let module = Module::from_user_binary(&bin, debug_funcs, Some(stylus_data))?;
+        let module = Module::from_user_binary(&bin, version, debug_funcs, Some(stylus_data))?;
         let hash = module.hash();
         self.add_stylus_module(hash, module.into_bytes());
         Ok(hash)
@@ -2702,6 +2704,17 @@ impl Machine {
                 }
                 Ok(())
             }
+            ("console", "debug_assert") => {
+                // only surfaces the message, as the hostio's body traps just after
+                let cond = pull_arg!(2, I32);
+                let ptr = pull_arg!(1, I32);
+                let len = pull_arg!(0, I32);
+                if cond == 0 {
+                    let text = String::from_utf8_lossy(read_bytes_segment!(ptr, len));
+                    Self::say(format!("assertion failed: {text}"));
+                }
+                Ok(())
+            }
             _ => Ok(()),
         }
     }
@@ -30,6 +30,21 @@ pub struct StylusConfig {
     pub max_depth: u32,
     /// Pricing parameters supplied at runtime
     pub pricing: PricingParams,
+    /// The gas below which requests to the EVM are refused, `0` meaning no reservation
+    pub min_gas_reserve: u64,
+    /// The maximum length of each modexp operand in bytes, `0` meaning no cap, which Geth never
+    /// sets
+    pub max_modexp_len: u32,
+    /// Whether `say` is muted while proving, leaving the other debug hostios available
+    pub suppress_say: bool,
+    /// The seed of each program's pseudo-random number generator in fuzzing builds
+    pub fuzz_seed: u64,
+    /// The most bytes of a hostio's args and outs recorded when tracing, `0` meaning no limit.
+    /// The hostio itself always sees the full data
+    pub max_trace_arg_bytes: u32,
+    /// The call depth past which the EVM refuses further calls, which bounds how deeply programs
+    /// may nest. Geth enforces it, so this only informs programs of how close they are
+    pub max_call_depth: u32,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -45,6 +60,12 @@ impl Default for StylusConfig {
             version: 0,
             max_depth: u32::MAX,
             pricing: PricingParams::default(),
+            min_gas_reserve: 0,
+            max_modexp_len: 0,
+            suppress_say: false,
+            fuzz_seed: 0,
+            max_trace_arg_bytes: 0,
+            max_call_depth: Self::DEFAULT_MAX_CALL_DEPTH,
         }
     }
 }
@@ -56,12 +77,21 @@ impl Default for PricingParams {
 }
 
 impl StylusConfig {
+    /// The EVM's call depth limit, Geth's `CallCreateDepth`, which Go's native config sets too.
+    pub const DEFAULT_MAX_CALL_DEPTH: u32 = 1024;
+
     pub const fn new(version: u16, max_depth: u32, ink_price: u32) -> Self {
         let pricing = PricingParams::new(ink_price);
         Self {
             version,
             max_depth,
             pricing,
+            min_gas_reserve: 0,
+            max_modexp_len: 0,
+            suppress_say: false,
+            fuzz_seed: 0,
+            max_trace_arg_bytes: 0,
+            max_call_depth: Self::DEFAULT_MAX_CALL_DEPTH,
         }
     }
 }
@@ -162,7 +192,8 @@ impl CompileConfig {
 
         match version {
             0 => {}
-            1 | 2 => {
+            1..=3 => {
+                // version 3 is unscheduled, existing only to gate newer hostios like mcopy and selfdestruct
                 config.bounds.heap_bound = Pages(128); // 8 mb
                 config.bounds.max_frame_size = 10 * 1024;
                 config.bounds.max_frame_contention = 4096;
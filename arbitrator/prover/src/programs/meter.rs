@@ -282,7 +282,7 @@ pub trait MeteredMachine {
 
     /// Pays for a read into the host.
     fn pay_for_read(&mut self, bytes: u32) -> Result<(), OutOfInkError> {
-        self.buy_ink(sat_add_mul(16381, 55, bytes.saturating_sub(32)))
+        self.buy_ink(read_ink(bytes))
     }
 
     /// Pays for both I/O and keccak.
@@ -338,6 +338,11 @@ pub trait GasMeteredMachine: MeteredMachine {
     }
 }
 
+/// The ink cost of reading `bytes` into the host, as charged by [`MeteredMachine::pay_for_read`].
+pub fn read_ink(bytes: u32) -> u64 {
+    sat_add_mul(16381, 55, bytes.saturating_sub(32))
+}
+
 fn sat_add_mul(base: u64, per: u64, count: u32) -> u64 {
     base.saturating_add(per.saturating_mul(count.into()))
 }
@@ -530,3 +535,23 @@ pub fn pricing_v1(op: &Operator, tys: &HashMap<SignatureIndex, FunctionType>) ->
     };
     ink
 }
+
+#[test]
+fn test_read_ink() {
+    impl MeteredMachine for MachineMeter {
+        fn ink_left(&self) -> MachineMeter {
+            *self
+        }
+        fn set_meter(&mut self, meter: MachineMeter) {
+            *self = meter;
+        }
+    }
+
+    for bytes in [0, 32, 33, 1024, u32::MAX] {
+        let mut meter = MachineMeter::Ready(u64::MAX);
+        meter.pay_for_read(bytes).unwrap();
+        assert_eq!(u64::MAX - meter.ink(), read_ink(bytes));
+    }
+    assert_eq!(read_ink(32), 16381);
+    assert_eq!(read_ink(33), 16381 + 55);
+}
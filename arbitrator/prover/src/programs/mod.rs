@@ -471,7 +471,8 @@ impl Module {
         let code = bin.codes.iter().map(|x| x.expr.len()).saturating_sum() as u64;
         pay!(code.saturating_mul(535) / 1_000);
 
-        let module = Self::from_user_binary(&bin, compile.debug.debug_funcs, Some(stylus_data))
+        let debug_funcs = compile.debug.debug_funcs;
+        let module = Self::from_user_binary(&bin, version, debug_funcs, Some(stylus_data))
             .wrap_err("failed to build user module")?;
 
         Ok((module, stylus_data))
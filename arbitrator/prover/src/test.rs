@@ -69,3 +69,27 @@ pub fn test_compress() -> Result<()> {
     }
     Ok(())
 }
+
+#[test]
+pub fn reject_unscheduled_hostios() {
+    let wasm = as_wasm(
+        r#"
+        (module
+            (import "vm_hooks" "read_args" (func (param i32)))
+            (import "vm_hooks" "mcopy" (func (param i32 i32 i32)))
+        )"#,
+    );
+    let bin = binary::parse(&wasm, Path::new("")).unwrap();
+    bin.check_hostio_versions(2).unwrap_err();
+    bin.check_hostio_versions(3).unwrap();
+
+    let wasm = as_wasm(
+        r#"
+        (module
+            (import "vm_hooks" "selfdestruct" (func (param i32)))
+        )"#,
+    );
+    let bin = binary::parse(&wasm, Path::new("")).unwrap();
+    bin.check_hostio_versions(2).unwrap_err();
+    bin.check_hostio_versions(3).unwrap();
+}
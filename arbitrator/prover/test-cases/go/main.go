@@ -95,13 +95,15 @@ func callStylusProgram(recurse int) {
 		panic("unsupported call")
 	}
 	calldata := common.Hash{}.Bytes()
+	var flushed []byte
 	_, _, err := programs.CallProgramLoop(
 		stylusModuleHash,
 		calldata,
 		160000000,
 		&evmData,
 		&progParams,
-		reqHandler)
+		reqHandler,
+		&flushed)
 	if err != nil {
 		panic(err)
 	}
@@ -45,6 +45,10 @@ where
         &self.evm_data
     }
 
+    fn min_gas_reserve(&self) -> u64 {
+        self.config().min_gas_reserve
+    }
+
     fn evm_return_data_len(&mut self) -> &mut u32 {
         &mut self.evm_data.return_data_len
     }
@@ -83,6 +87,9 @@ where
 
     fn trace(&mut self, name: &str, args: &[u8], outs: &[u8], end_ink: u64) {
         let start_ink = self.start_ink;
+        let limit = self.config().max_trace_arg_bytes;
+        let args = &arbutil::truncate_trace_data(args, limit);
+        let outs = &arbutil::truncate_trace_data(outs, limit);
         self.evm_api
             .capture_hostio(name, args, outs, start_ink, end_ink);
     }
@@ -260,6 +267,17 @@ pub(crate) fn account_balance<D: DataReader, E: EvmApi<D>>(
     hostio!(env, account_balance(address, ptr))
 }
 
+pub(crate) fn selfdestruct<D: DataReader, E: EvmApi<D>>(
+    mut env: WasmEnvMut<D, E>,
+    beneficiary: GuestPtr,
+) -> MaybeEscape {
+    hostio!(env, selfdestruct(beneficiary))
+}
+
+pub(crate) fn flush_output<D: DataReader, E: EvmApi<D>>(mut env: WasmEnvMut<D, E>) -> MaybeEscape {
+    hostio!(env, flush_output())
+}
+
 pub(crate) fn account_code<D: DataReader, E: EvmApi<D>>(
     mut env: WasmEnvMut<D, E>,
     address: GuestPtr,
@@ -413,6 +431,15 @@ pub(crate) fn native_keccak256<D: DataReader, E: EvmApi<D>>(
     hostio!(env, native_keccak256(input, len, output))
 }
 
+pub(crate) fn mcopy<D: DataReader, E: EvmApi<D>>(
+    mut env: WasmEnvMut<D, E>,
+    dest: GuestPtr,
+    src: GuestPtr,
+    len: u32,
+) -> MaybeEscape {
+    hostio!(env, mcopy(dest, src, len))
+}
+
 pub(crate) fn tx_gas_price<D: DataReader, E: EvmApi<D>>(
     mut env: WasmEnvMut<D, E>,
     ptr: GuestPtr,
@@ -506,6 +533,15 @@ pub(crate) mod console {
     ) -> Result<f64, Escape> {
         hostio!(env, console_tee(value))
     }
+
+    pub(crate) fn debug_assert<D: DataReader, E: EvmApi<D>>(
+        mut env: WasmEnvMut<D, E>,
+        cond: u32,
+        ptr: GuestPtr,
+        len: u32,
+    ) -> MaybeEscape {
+        hostio!(env, debug_assert(cond, ptr, len))
+    }
 }
 
 pub(crate) mod debug {
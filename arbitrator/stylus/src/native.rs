@@ -189,7 +189,7 @@ impl<D: DataReader, E: EvmApi<D>> NativeInstance<D, E> {
                 msg_reentrant, msg_sender, msg_value,
                 tx_gas_price, tx_ink_price, tx_origin,
                 pay_for_memory_grow,
-                native_keccak256,
+                native_keccak256, mcopy, selfdestruct, flush_output,
             },
         );
         if debug_funcs {
@@ -198,6 +198,7 @@ impl<D: DataReader, E: EvmApi<D>> NativeInstance<D, E> {
                     log_txt,
                     log_i32, log_i64, log_f32, log_f64,
                     tee_i32, tee_i64, tee_f32, tee_f64,
+                    debug_assert,
                 },
                 "debug" => host::debug {
                     null_host,
@@ -10,7 +10,7 @@ use arbutil::{
     },
     Bytes20, Bytes32,
 };
-use eyre::Result;
+use eyre::{bail, Result};
 use parking_lot::Mutex;
 use prover::programs::{memory::MemoryModel, prelude::*};
 use std::{collections::HashMap, sync::Arc};
@@ -28,6 +28,9 @@ pub(crate) struct TestEvmApi {
     configs: Arc<Mutex<HashMap<Bytes20, StylusConfig>>>,
     evm_data: EvmData,
     pages: Arc<Mutex<(u16, u16)>>,
+    balances: Arc<Mutex<HashMap<Bytes20, u64>>>,
+    read_only: Arc<Mutex<bool>>,
+    flushed: Arc<Mutex<Vec<u8>>>,
 }
 
 impl TestEvmApi {
@@ -47,6 +50,9 @@ impl TestEvmApi {
             configs: Arc::new(Mutex::new(HashMap::new())),
             evm_data,
             pages: Arc::new(Mutex::new((0, 0))),
+            balances: Arc::new(Mutex::new(HashMap::new())),
+            read_only: Arc::new(Mutex::new(false)),
+            flushed: Arc::new(Mutex::new(vec![])),
         };
         (api, evm_data)
     }
@@ -65,6 +71,28 @@ impl TestEvmApi {
         pages.0 = open;
         pages.1 = open.max(pages.1);
     }
+
+    pub fn set_balance(&mut self, address: Bytes20, balance: u64) {
+        self.balances.lock().insert(address, balance);
+    }
+
+    pub fn balance(&self, address: Bytes20) -> u64 {
+        self.balances
+            .lock()
+            .get(&address)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Makes state-changing requests fail, as they do in a static context.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        *self.read_only.lock() = read_only;
+    }
+
+    /// Provides the output flushed so far, which precedes the program's final output.
+    pub fn flushed(&self) -> Vec<u8> {
+        self.flushed.lock().clone()
+    }
 }
 
 impl EvmApi<VecReader> for TestEvmApi {
@@ -176,8 +204,23 @@ impl EvmApi<VecReader> for TestEvmApi {
         Ok(()) // pretend a log was emitted
     }
 
-    fn account_balance(&mut self, _address: Bytes20) -> (Bytes32, u64) {
-        unimplemented!()
+    fn account_balance(&mut self, address: Bytes20) -> (Bytes32, u64) {
+        (self.balance(address).into(), 2600) // pretend worst case
+    }
+
+    fn self_destruct(&mut self, beneficiary: Bytes20) -> Result<u64> {
+        if *self.read_only.lock() {
+            bail!("write protection");
+        }
+        let mut balances = self.balances.lock();
+        let balance = balances.remove(&self.program).unwrap_or_default();
+        *balances.entry(beneficiary).or_default() += balance;
+        Ok(2600) // pretend worst case
+    }
+
+    fn flush_output(&mut self, data: &[u8]) -> Result<()> {
+        self.flushed.lock().extend(data);
+        Ok(())
     }
 
     fn account_code(&mut self, _address: Bytes20, _gas_left: u64) -> (VecReader, u64) {
@@ -188,6 +231,18 @@ impl EvmApi<VecReader> for TestEvmApi {
         unimplemented!()
     }
 
+    fn account_code_size(&mut self, _address: Bytes20) -> (u32, u64) {
+        unimplemented!()
+    }
+
+    fn block_hash(&mut self, _number: u64) -> (Bytes32, u64) {
+        unimplemented!()
+    }
+
+    fn prefetch_bytes32(&mut self, _keys: &[Bytes32]) -> u64 {
+        unimplemented!()
+    }
+
     fn add_pages(&mut self, new: u16) -> u64 {
         let model = MemoryModel::new(2, 1000);
         let (open, ever) = *self.pages.lock();
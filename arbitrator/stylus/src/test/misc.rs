@@ -80,3 +80,34 @@ fn test_console() -> Result<()> {
     machine.call_user_func(StartMover::NAME, vec![], ink)?;
     check_instrumentation(native, machine)
 }
+
+#[test]
+fn test_debug_assert() -> Result<()> {
+    let filename = "tests/debug-assert.wat";
+    let (compile, config, ink) = test_configs();
+
+    let mut native = NativeInstance::new_linked(filename, &compile, config)?;
+    let mut machine = new_test_machine(filename, &compile)?;
+
+    let exports = &native.instance.exports;
+    let pass = exports.get_typed_function::<(), ()>(&native.store, "pass")?;
+    let fail = exports.get_typed_function::<(), ()>(&native.store, "fail")?;
+    let oob = exports.get_typed_function::<(), ()>(&native.store, "oob")?;
+
+    native.call_func(pass, ink)?;
+    machine.call_user_func("pass", vec![], ink)?;
+
+    // a false assertion surfaces its message
+    let err = format!("{:?}", native.call_func(fail, ink).unwrap_err());
+    assert!(err.contains("assertion failed: ink ran dry"));
+    drop(machine.call_user_func("fail", vec![], ink).unwrap_err());
+
+    // the message is bounds-checked
+    native.call_func(oob, ink).unwrap_err();
+
+    // outside of debug mode, neither side links the hostio
+    let compile = CompileConfig::version(compile.version, false);
+    NativeInstance::new_linked(filename, &compile, config).unwrap_err();
+    new_test_machine(filename, &compile).unwrap_err();
+    Ok(())
+}
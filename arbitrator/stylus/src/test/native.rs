@@ -470,6 +470,124 @@ fn test_calls() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_mcopy() -> Result<()> {
+    // in mcopy.wat
+    //     the input is copied 4 bytes forward onto itself
+    //     everything written is returned as the output
+
+    let (compile, config, ink) = test_configs();
+    let args: Vec<u8> = (1..=8).collect();
+
+    let mut native = TestInstance::new_linked("tests/mcopy.wat", &compile, config)?;
+    let output = match native.run_main(&args, config, ink)? {
+        UserOutcome::Success(output) => output,
+        err => bail!("expected success: {}", err.red()),
+    };
+
+    // as with the EVM's MCOPY, overlapping regions copy as if through a buffer
+    let expected = [&args[..4], &args].concat();
+    assert_eq!(hex::encode(output), hex::encode(expected));
+    Ok(())
+}
+
+/// Activates the program in a wat file as Geth would, at the given Stylus version.
+fn activate_wat(file: &str, version: u16) -> Result<()> {
+    let wat = std::fs::read(file)?;
+    let wasm = wasmer::wat2wasm(&wat)?;
+    let mut gas = u64::MAX;
+    crate::native::activate(&wasm, &Bytes32::default(), version, 128, true, &mut gas)?;
+    Ok(())
+}
+
+#[test]
+fn test_mcopy_version() -> Result<()> {
+    // mcopy is unavailable before Stylus version 3, so activation rejects programs importing it
+    for version in 1..3 {
+        let err = activate_wat("tests/mcopy.wat", version).unwrap_err();
+        let expected = format!("unavailable in Stylus version {version}");
+        assert!(format!("{err:?}").contains(&expected), "{err:?}");
+    }
+    activate_wat("tests/mcopy.wat", 3)
+}
+
+#[test]
+fn test_selfdestruct() -> Result<()> {
+    // in selfdestruct.wat
+    //     the args are the beneficiary, to whom the contract's balance is sent
+
+    let (compile, config, _) = test_configs();
+    let ink = config.pricing.gas_to_ink(1_000_000);
+    let contract = Bytes20::default();
+    let beneficiary = random_bytes20();
+
+    // a static context refuses to move the balance
+    let filename = "tests/selfdestruct.wat";
+    let (mut native, mut evm) = TestInstance::new_with_evm(filename, &compile, config)?;
+    evm.set_balance(contract, 1000);
+    evm.set_read_only(true);
+    match native.run_main(&beneficiary.0, config, ink)? {
+        UserOutcome::Failure(_) => {}
+        outcome => bail!("static selfdestruct didn't fail: {}", outcome.red()),
+    }
+    assert_eq!(evm.balance(contract), 1000);
+    assert_eq!(evm.balance(beneficiary), 0);
+
+    let (mut native, mut evm) = TestInstance::new_with_evm(filename, &compile, config)?;
+    evm.set_balance(contract, 1000);
+    match native.run_main(&beneficiary.0, config, ink)? {
+        UserOutcome::Success(_) => {}
+        outcome => bail!("expected success: {}", outcome.red()),
+    }
+    assert_eq!(evm.balance(contract), 0);
+    assert_eq!(evm.balance(beneficiary), 1000);
+    Ok(())
+}
+
+#[test]
+fn test_selfdestruct_version() -> Result<()> {
+    // selfdestruct is unavailable before Stylus version 3, so activation rejects programs using it
+    for version in 1..3 {
+        let err = activate_wat("tests/selfdestruct.wat", version).unwrap_err();
+        let expected = format!("unavailable in Stylus version {version}");
+        assert!(format!("{err:?}").contains(&expected), "{err:?}");
+    }
+    activate_wat("tests/selfdestruct.wat", 3)
+}
+
+#[test]
+fn test_flush_output() -> Result<()> {
+    // in flush.wat
+    //     the first half of the args is flushed
+    //     the second half is returned as the output
+
+    let (compile, config, ink) = test_configs();
+    let args = b"hello world";
+
+    let (mut native, evm) = TestInstance::new_with_evm("tests/flush.wat", &compile, config)?;
+    let output = match native.run_main(args, config, ink)? {
+        UserOutcome::Success(output) => output,
+        err => bail!("expected success: {}", err.red()),
+    };
+
+    // the host prepends what was flushed, so the caller sees the args whole
+    assert_eq!(evm.flushed(), b"hello");
+    assert_eq!(output, b" world");
+    assert_eq!([evm.flushed(), output].concat(), args);
+    Ok(())
+}
+
+#[test]
+fn test_flush_output_version() -> Result<()> {
+    // flush_output is unavailable before Stylus version 3, so activation rejects programs using it
+    for version in 1..3 {
+        let err = activate_wat("tests/flush.wat", version).unwrap_err();
+        let expected = format!("unavailable in Stylus version {version}");
+        assert!(format!("{err:?}").contains(&expected), "{err:?}");
+    }
+    activate_wat("tests/flush.wat", 3)
+}
+
 #[test]
 fn test_exit_early() -> Result<()> {
     // in exit-early.wat
@@ -0,0 +1,17 @@
+;; Copyright 2024, Offchain Labs, Inc.
+;; For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+(module
+    (import "console" "debug_assert" (func $debug_assert (param i32 i32 i32)))
+    (memory (export "memory") 1 1)
+    (data (i32.const 0xa4b) "\69\6E\6B\20\72\61\6E\20\64\72\79") ;; ink ran dry
+    (func (export "pass")
+        (call $debug_assert (i32.const 1) (i32.const 0xa4b) (i32.const 11)))
+    (func (export "fail")
+        (call $debug_assert (i32.const 0) (i32.const 0xa4b) (i32.const 11)))
+    (func (export "oob")
+        (call $debug_assert (i32.const 0) (i32.const 0xfffe) (i32.const 11)))
+    (func (export "user_entrypoint") (param $args_len i32) (result i32)
+        (i32.const 0)
+    )
+)
@@ -6,6 +6,7 @@ use arbutil::{
     evm::{
         self,
         api::{DataReader, EvmApi},
+        req::RequestError,
         storage::StorageCache,
         user::UserOutcomeKind,
         EvmData,
@@ -68,6 +69,8 @@ pub trait UserHost<DR: DataReader>: GasMeteredMachine {
 
     fn evm_api(&mut self) -> &mut Self::A;
     fn evm_data(&self) -> &EvmData;
+    /// The gas below which requests to the EVM are refused, `0` meaning no reservation.
+    fn min_gas_reserve(&self) -> u64;
     fn evm_return_data_len(&mut self) -> &mut u32;
 
     fn read_slice(&self, ptr: GuestPtr, len: u32) -> Result<Vec<u8>, Self::MemoryErr>;
@@ -86,6 +89,36 @@ pub trait UserHost<DR: DataReader>: GasMeteredMachine {
         let value = self.read_bytes32(ptr)?;
         Ok((value.into(), value))
     }
+    fn read_u128(&self, ptr: GuestPtr) -> Result<u128, Self::MemoryErr> {
+        self.read_fixed(ptr).map(u128::from_le_bytes)
+    }
+    /// Reads an EVM word, preserving its big-endian byte order. Unlike [`read_u128`], which
+    /// decodes a little-endian WASM integer, no bytes are swapped.
+    ///
+    /// [`read_u128`]: UserHost::read_u128
+    fn read_u256_be(&self, ptr: GuestPtr) -> Result<Bytes32, Self::MemoryErr> {
+        self.read_bytes32(ptr)
+    }
+    /// Reads a UTF-8 encoded string, failing on invalid sequences rather than replacing them.
+    fn read_string(&self, ptr: GuestPtr, len: u32) -> Result<String, Self::Err> {
+        let text = self.read_slice(ptr, len)?;
+        String::from_utf8(text).map_err(|err| {
+            let offset = err.utf8_error().valid_up_to();
+            eyre!("invalid UTF-8 at byte {offset} of string at {}", ptr.0).into()
+        })
+    }
+
+    /// Provides the EVM API for making a request, refusing once the gas left on the meter dips
+    /// below the [`min_gas_reserve`] so that a call is never left stuck mid-request without gas.
+    ///
+    /// [`min_gas_reserve`]: UserHost::min_gas_reserve
+    fn request_evm_api(&mut self) -> Result<&mut Self::A, Self::Err> {
+        let gas_left = self.gas_left()?;
+        if let Err(error) = RequestError::check_gas_reserve(gas_left, self.min_gas_reserve()) {
+            return Err(eyre::ErrReport::from(error).into());
+        }
+        Ok(self.evm_api())
+    }
 
     fn say<D: Display>(&self, text: D);
     fn trace(&mut self, name: &str, args: &[u8], outs: &[u8], end_ink: u64);
@@ -96,6 +129,25 @@ pub trait UserHost<DR: DataReader>: GasMeteredMachine {
     fn write_bytes32(&self, ptr: GuestPtr, src: Bytes32) -> Result<(), Self::MemoryErr> {
         self.write_slice(ptr, &src.0)
     }
+    fn write_u128(&self, ptr: GuestPtr, x: u128) -> Result<(), Self::MemoryErr> {
+        self.write_slice(ptr, &x.to_le_bytes())
+    }
+    /// Writes an EVM word, preserving its big-endian byte order. Unlike [`write_u128`], which
+    /// encodes a little-endian WASM integer, no bytes are swapped.
+    ///
+    /// [`write_u128`]: UserHost::write_u128
+    fn write_u256_be(&self, ptr: GuestPtr, value: Bytes32) -> Result<(), Self::MemoryErr> {
+        self.write_bytes32(ptr, value)
+    }
+
+    /// Writes to guest memory, paying for the write before any bytes are copied. A program without
+    /// the ink to afford it runs out before its memory is modified.
+    fn write_slice_metered(&mut self, ptr: GuestPtr, src: &[u8]) -> Result<(), Self::Err> {
+        let len = u32::try_from(src.len()).map_err(|_| eyre!("write of {} bytes", src.len()))?;
+        self.pay_for_write(len)?;
+        self.write_slice(ptr, src)?;
+        Ok(())
+    }
 
     /// Reads the program calldata. The semantics are equivalent to that of the EVM's
     /// [`CALLDATA_COPY`] opcode when requesting the entirety of the current call's calldata.
@@ -147,12 +199,22 @@ pub trait UserHost<DR: DataReader>: GasMeteredMachine {
 
         let key = self.read_bytes32(key)?;
 
-        let (value, gas_cost) = self.evm_api().get_bytes32(key);
+        let (value, gas_cost) = self.request_evm_api()?.get_bytes32(key);
         self.buy_gas(gas_cost)?;
         self.write_bytes32(dest, value)?;
         trace!("storage_load_bytes32", self, key, value)
     }
 
+    /// Loads a set of storage slots into the storage cache with a single request, paying their
+    /// cold access costs up front so that later calls to `storage_load_bytes32` are cheap.
+    /// Unlike the hostios, the keys come from the host rather than guest memory.
+    fn prefetch_storage(&mut self, keys: &[Bytes32]) -> Result<(), Self::Err> {
+        self.buy_ink(HOSTIO_INK)?;
+        let gas_cost = self.request_evm_api()?.prefetch_bytes32(keys);
+        self.buy_gas(gas_cost)?;
+        Ok(())
+    }
+
     /// Writes a 32-byte value to the permanent storage cache. Stylus's storage format is identical to that
     /// of the EVM. This means that, under the hood, this hostio represents storing a 32-byte value into
     /// the EVM state trie at offset `key`. Refunds are tabulated exactly as in the EVM. The semantics, then,
@@ -185,7 +247,8 @@ pub trait UserHost<DR: DataReader>: GasMeteredMachine {
         self.require_gas(evm::SSTORE_SENTRY_GAS)?; // see operations_acl_arbitrum.go
 
         let gas_left = self.gas_left()?;
-        self.evm_api().flush_storage_cache(clear, gas_left)?;
+        self.request_evm_api()?
+            .flush_storage_cache(clear, gas_left)?;
         trace!("storage_flush_cache", self, [be!(clear as u8)], &[])
     }
 
@@ -200,7 +263,7 @@ pub trait UserHost<DR: DataReader>: GasMeteredMachine {
         self.buy_gas(evm::TLOAD_GAS)?;
 
         let key = self.read_bytes32(key)?;
-        let value = self.evm_api().get_transient_bytes32(key);
+        let value = self.request_evm_api()?.get_transient_bytes32(key);
         self.write_bytes32(dest, value)?;
         trace!("transient_load_bytes32", self, key, value)
     }
@@ -217,7 +280,7 @@ pub trait UserHost<DR: DataReader>: GasMeteredMachine {
 
         let key = self.read_bytes32(key)?;
         let value = self.read_bytes32(value)?;
-        self.evm_api().set_transient_bytes32(key, value)?;
+        self.request_evm_api()?.set_transient_bytes32(key, value)?;
         trace!("transient_store_bytes32", self, [key, value], &[])
     }
 
@@ -341,7 +404,7 @@ pub trait UserHost<DR: DataReader>: GasMeteredMachine {
         let contract = self.read_bytes20(contract)?;
         let input = self.read_slice(calldata, calldata_len)?;
         let value = value.map(|x| self.read_bytes32(x)).transpose()?;
-        let api = self.evm_api();
+        let api = self.request_evm_api()?;
 
         let (outs_len, gas_cost, status) = call(api, contract, &input, gas_left, gas_req, value);
         self.buy_gas(gas_cost)?;
@@ -470,7 +533,7 @@ pub trait UserHost<DR: DataReader>: GasMeteredMachine {
         let endowment = self.read_bytes32(endowment)?;
         let salt = salt.map(|x| self.read_bytes32(x)).transpose()?;
         let gas = self.gas_left()?;
-        let api = self.evm_api();
+        let api = self.request_evm_api()?;
 
         let (result, ret_len, gas_cost) = call(api, code, endowment, salt, gas);
         let result = result?;
@@ -556,7 +619,7 @@ pub trait UserHost<DR: DataReader>: GasMeteredMachine {
         self.pay_for_evm_log(topics, len - topics * 32)?;
 
         let data = self.read_slice(data, len)?;
-        self.evm_api().emit_log(data.clone(), topics)?;
+        self.request_evm_api()?.emit_log(data.clone(), topics)?;
         trace!("emit_log", self, [be!(topics), data], &[])
     }
 
@@ -569,12 +632,30 @@ pub trait UserHost<DR: DataReader>: GasMeteredMachine {
         self.require_gas(evm::COLD_ACCOUNT_GAS)?;
         let address = self.read_bytes20(address)?;
 
-        let (balance, gas_cost) = self.evm_api().account_balance(address);
+        let (balance, gas_cost) = self.request_evm_api()?.account_balance(address);
         self.buy_gas(gas_cost)?;
         self.write_bytes32(ptr, balance)?;
         trace!("account_balance", self, address, balance)
     }
 
+    /// Destroys the current contract, sending its balance to the `beneficiary`. Since Cancun, per
+    /// EIP-6780, the account's code and storage are only deleted when the contract was created
+    /// earlier in the same transaction. Otherwise the balance moves but the contract remains.
+    /// The semantics are otherwise equivalent to that of the EVM's [`SELFDESTRUCT`] opcode. Note
+    /// that, unlike the opcode, this hostio does not halt execution.
+    ///
+    /// [`SELFDESTRUCT`]: https://www.evm.codes/#ff
+    fn selfdestruct(&mut self, beneficiary: GuestPtr) -> Result<(), Self::Err> {
+        self.buy_ink(HOSTIO_INK + PTR_INK + EVM_API_INK)?;
+        self.buy_gas(evm::SELFDESTRUCT_GAS)?;
+        self.require_gas(evm::COLD_ACCOUNT_GAS)?;
+        let beneficiary = self.read_bytes20(beneficiary)?;
+
+        let gas_cost = self.request_evm_api()?.self_destruct(beneficiary)?;
+        self.buy_gas(gas_cost)?;
+        trace!("selfdestruct", self, beneficiary, &[])
+    }
+
     /// Gets a subset of the code from the account at the given address. The semantics are identical to that
     /// of the EVM's [`EXT_CODE_COPY`] opcode, aside from one small detail: the write to the buffer `dest` will
     /// stop after the last byte is written. This is unlike the EVM, which right pads with zeros in this scenario.
@@ -595,7 +676,7 @@ pub trait UserHost<DR: DataReader>: GasMeteredMachine {
         let gas = self.gas_left()?;
 
         // we pass `gas` to check if there's enough before loading from the db
-        let (code, gas_cost) = self.evm_api().account_code(address, gas);
+        let (code, gas_cost) = self.request_evm_api()?.account_code(address, gas);
         self.buy_gas(gas_cost)?;
 
         let code = code.slice();
@@ -625,7 +706,7 @@ pub trait UserHost<DR: DataReader>: GasMeteredMachine {
         let gas = self.gas_left()?;
 
         // we pass `gas` to check if there's enough before loading from the db
-        let (code, gas_cost) = self.evm_api().account_code(address, gas);
+        let (code, gas_cost) = self.request_evm_api()?.account_code(address, gas);
         self.buy_gas(gas_cost)?;
 
         let code = code.slice();
@@ -644,7 +725,7 @@ pub trait UserHost<DR: DataReader>: GasMeteredMachine {
         self.require_gas(evm::COLD_ACCOUNT_GAS)?;
         let address = self.read_bytes20(address)?;
 
-        let (hash, gas_cost) = self.evm_api().account_codehash(address);
+        let (hash, gas_cost) = self.request_evm_api()?.account_codehash(address);
         self.buy_gas(gas_cost)?;
         self.write_bytes32(ptr, hash)?;
         trace!("account_codehash", self, address, hash)
@@ -890,6 +971,30 @@ pub trait UserHost<DR: DataReader>: GasMeteredMachine {
         trace!("native_keccak256", self, preimage, digest)
     }
 
+    /// Copies `len` bytes within the program's memory from `src` to `dest`. The regions may
+    /// overlap, in which case the copy happens as if through an intermediate buffer. The
+    /// semantics are equivalent to that of the EVM's [`MCOPY`] opcode.
+    ///
+    /// [`MCOPY`]: https://www.evm.codes/#5e
+    fn mcopy(&mut self, dest: GuestPtr, src: GuestPtr, len: u32) -> Result<(), Self::Err> {
+        self.buy_ink(HOSTIO_INK + 2 * PTR_INK)?;
+        self.pay_for_read(len)?;
+        self.pay_for_write(len)?;
+
+        let data = self.read_slice(src, len)?;
+        self.write_slice(dest, &data)?;
+        trace!("mcopy", self, [be!(dest.0), be!(src.0), be!(len)], &[])
+    }
+
+    /// Sends the output so far to the host and clears it, so that a large output needn't be
+    /// buffered whole. The host prepends whatever was flushed to the program's final output.
+    fn flush_output(&mut self) -> Result<(), Self::Err> {
+        self.buy_ink(HOSTIO_INK + EVM_API_INK)?;
+        let outs = std::mem::take(self.outs());
+        self.request_evm_api()?.flush_output(&outs)?;
+        trace!("flush_output", self, &[], &[])
+    }
+
     /// Gets the gas price in wei per gas, which on Arbitrum chains equals the basefee. The
     /// semantics are equivalent to that of the EVM's [`GAS_PRICE`] opcode.
     ///
@@ -926,7 +1031,7 @@ pub trait UserHost<DR: DataReader>: GasMeteredMachine {
             self.buy_ink(HOSTIO_INK)?;
             return Ok(());
         }
-        let gas_cost = self.evm_api().add_pages(pages); // no sentry needed since the work happens after the hostio
+        let gas_cost = self.request_evm_api()?.add_pages(pages); // no sentry needed since the work happens after the hostio
         self.buy_gas(gas_cost)?;
         trace!("pay_for_memory_grow", self, be!(pages), &[])
     }
@@ -950,4 +1055,15 @@ pub trait UserHost<DR: DataReader>: GasMeteredMachine {
         self.say(value.into());
         Ok(value)
     }
+
+    /// Aborts the program with a UTF-8 encoded message if the condition is zero.
+    /// Only available in debug mode, where the prover traps just the same.
+    fn debug_assert(&mut self, cond: u32, ptr: GuestPtr, len: u32) -> Result<(), Self::Err> {
+        if cond != 0 {
+            return Ok(());
+        }
+        let text = self.read_slice(ptr, len)?;
+        let text = String::from_utf8_lossy(&text);
+        Err(eyre!("assertion failed: {text}").into())
+    }
 }
@@ -0,0 +1,221 @@
+// Copyright 2024, Offchain Labs, Inc.
+// For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+//! Snapshots of a program's state for post-mortem debugging.
+//!
+//! Memory isn't included, so a dump can be inspected but never resumed.
+
+use arbutil::{evm::EvmData, Bytes20, Bytes32};
+use prover::programs::{config::PricingParams, prelude::StylusConfig};
+use std::fmt::Display;
+
+/// Bumped whenever the encoding changes.
+const DUMP_VERSION: u8 = 1;
+
+/// The state of a program at the time it was dumped.
+#[derive(Clone, Debug)]
+pub(crate) struct ProgramDump {
+    pub args: Vec<u8>,
+    pub outs: Vec<u8>,
+    pub evm_data: EvmData,
+    pub module: u32,
+    pub config: StylusConfig,
+    /// The type and payload of the request awaiting a response, if any.
+    pub pending_request: Option<(u32, Vec<u8>)>,
+}
+
+/// Reasons a dump may fail to decode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum DecodeError {
+    /// The dump ended early.
+    Truncated,
+    /// The dump was written by an incompatible version.
+    UnknownVersion(u8),
+    /// A boolean was neither `0` nor `1`.
+    InvalidFlag(u8),
+    /// Bytes remained after the dump was fully decoded.
+    TrailingBytes(usize),
+}
+
+impl std::error::Error for DecodeError {}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "dump truncated"),
+            Self::UnknownVersion(version) => write!(f, "unknown dump version {version}"),
+            Self::InvalidFlag(flag) => write!(f, "invalid flag {flag}"),
+            Self::TrailingBytes(count) => write!(f, "{count} trailing bytes after dump"),
+        }
+    }
+}
+
+impl ProgramDump {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = vec![DUMP_VERSION];
+        put_bytes(&mut data, &self.args);
+        put_bytes(&mut data, &self.outs);
+
+        let evm = &self.evm_data;
+        data.extend(evm.block_basefee);
+        data.extend(evm.chainid.to_be_bytes());
+        data.extend(evm.block_coinbase);
+        data.extend(evm.block_gas_limit.to_be_bytes());
+        data.extend(evm.block_number.to_be_bytes());
+        data.extend(evm.block_timestamp.to_be_bytes());
+        data.extend(evm.block_randomness);
+        data.extend(evm.contract_address);
+        data.extend(evm.module_hash);
+        data.extend(evm.msg_sender);
+        data.extend(evm.msg_value);
+        data.extend(evm.tx_gas_price);
+        data.extend(evm.tx_origin);
+        data.extend(evm.reentrant.to_be_bytes());
+        data.extend(evm.return_data_len.to_be_bytes());
+        data.push(evm.cached as u8);
+        data.push(evm.tracing as u8);
+        data.push(evm.read_only as u8);
+
+        data.extend(self.module.to_be_bytes());
+
+        let config = &self.config;
+        data.extend(config.version.to_be_bytes());
+        data.extend(config.max_depth.to_be_bytes());
+        data.extend(config.pricing.ink_price.to_be_bytes());
+        data.extend(config.min_gas_reserve.to_be_bytes());
+        data.extend(config.max_modexp_len.to_be_bytes());
+        data.push(config.suppress_say as u8);
+        data.extend(config.fuzz_seed.to_be_bytes());
+        data.extend(config.max_trace_arg_bytes.to_be_bytes());
+        data.extend(config.max_call_depth.to_be_bytes());
+
+        match &self.pending_request {
+            Some((req_type, req_data)) => {
+                data.push(1);
+                data.extend(req_type.to_be_bytes());
+                put_bytes(&mut data, req_data);
+            }
+            None => data.push(0),
+        }
+        data
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = DumpReader(data);
+        let version = reader.u8()?;
+        if version != DUMP_VERSION {
+            return Err(DecodeError::UnknownVersion(version));
+        }
+        let args = reader.bytes()?;
+        let outs = reader.bytes()?;
+
+        let evm_data = EvmData {
+            block_basefee: reader.bytes32()?,
+            chainid: reader.u64()?,
+            block_coinbase: reader.bytes20()?,
+            block_gas_limit: reader.u64()?,
+            block_number: reader.u64()?,
+            block_timestamp: reader.u64()?,
+            block_randomness: reader.bytes32()?,
+            contract_address: reader.bytes20()?,
+            module_hash: reader.bytes32()?,
+            msg_sender: reader.bytes20()?,
+            msg_value: reader.bytes32()?,
+            tx_gas_price: reader.bytes32()?,
+            tx_origin: reader.bytes20()?,
+            reentrant: reader.u32()?,
+            return_data_len: reader.u32()?,
+            cached: reader.bool()?,
+            tracing: reader.bool()?,
+            read_only: reader.bool()?,
+        };
+
+        let module = reader.u32()?;
+
+        let config = StylusConfig {
+            version: u16::from_be_bytes(reader.fixed()?),
+            max_depth: reader.u32()?,
+            pricing: PricingParams::new(reader.u32()?),
+            min_gas_reserve: reader.u64()?,
+            max_modexp_len: reader.u32()?,
+            suppress_say: reader.bool()?,
+            fuzz_seed: reader.u64()?,
+            max_trace_arg_bytes: reader.u32()?,
+            max_call_depth: reader.u32()?,
+        };
+
+        let pending_request = match reader.bool()? {
+            true => Some((reader.u32()?, reader.bytes()?)),
+            false => None,
+        };
+
+        if !reader.0.is_empty() {
+            return Err(DecodeError::TrailingBytes(reader.0.len()));
+        }
+        Ok(Self {
+            args,
+            outs,
+            evm_data,
+            module,
+            config,
+            pending_request,
+        })
+    }
+}
+
+/// Writes a length-prefixed byte string.
+fn put_bytes(data: &mut Vec<u8>, bytes: &[u8]) {
+    data.extend((bytes.len() as u32).to_be_bytes());
+    data.extend(bytes);
+}
+
+/// Consumes a dump from the front.
+struct DumpReader<'a>(&'a [u8]);
+
+impl DumpReader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], DecodeError> {
+        if self.0.len() < len {
+            return Err(DecodeError::Truncated);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn fixed<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(u32::from_be_bytes(self.fixed()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(u64::from_be_bytes(self.fixed()?))
+    }
+
+    fn bool(&mut self) -> Result<bool, DecodeError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            flag => Err(DecodeError::InvalidFlag(flag)),
+        }
+    }
+
+    fn bytes20(&mut self) -> Result<Bytes20, DecodeError> {
+        Ok(Bytes20(self.fixed()?))
+    }
+
+    fn bytes32(&mut self) -> Result<Bytes32, DecodeError> {
+        Ok(Bytes32(self.fixed()?))
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, DecodeError> {
+        let len = self.u32()? as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+}
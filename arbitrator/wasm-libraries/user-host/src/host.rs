@@ -1,26 +1,90 @@
 // Copyright 2022-2024, Offchain Labs, Inc.
 // For license information, see https://github.com/nitro/blob/master/LICENSE
 
-use crate::program::Program;
-use arbutil::evm::user::UserOutcomeKind;
+use crate::program::{set_trap, Program};
+use arbutil::evm::{api::EvmApiMethod, user::UserOutcomeKind};
 use caller_env::GuestPtr;
 use user_host_trait::UserHost;
 
-#[link(wasm_import_module = "forward")]
-extern "C" {
-    fn set_trap();
+#[cfg(feature = "diagnostics")]
+use std::sync::Once;
+
+/// The hostio currently executing, if any, for inclusion in panic messages.
+#[cfg(feature = "diagnostics")]
+static mut ACTIVE_HOSTIO: Option<&'static str> = None;
+
+/// Marks a hostio as executing until dropped, restoring whichever was before.
+/// Hostios nest when calls into other contracts run programs of their own.
+#[cfg(feature = "diagnostics")]
+struct ActiveHostio(Option<&'static str>);
+
+#[cfg(feature = "diagnostics")]
+impl ActiveHostio {
+    fn enter(name: &'static str) -> Self {
+        install_panic_hook();
+        unsafe {
+            let prior = ACTIVE_HOSTIO;
+            ACTIVE_HOSTIO = Some(name);
+            Self(prior)
+        }
+    }
+}
+
+#[cfg(feature = "diagnostics")]
+impl Drop for ActiveHostio {
+    fn drop(&mut self) {
+        unsafe { ACTIVE_HOSTIO = self.0 }
+    }
+}
+
+/// Provides the hostio currently executing, if any.
+#[cfg(feature = "diagnostics")]
+pub(crate) fn active_hostio() -> Option<&'static str> {
+    unsafe { ACTIVE_HOSTIO }
+}
+
+/// Augments panic messages with the active hostio and the number of active programs.
+#[cfg(feature = "diagnostics")]
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let default = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if let Some(name) = active_hostio() {
+                let depth = Program::depth();
+                eprintln!("panicked in hostio {name} at program depth {depth}");
+            }
+            default(info);
+        }));
+    });
 }
 
 macro_rules! hostio {
-    ($($func:tt)*) => {
-        match Program::current().$($func)* {
+    ($func:ident($($arg:expr),* $(,)?)) => {{
+        #[cfg(feature = "diagnostics")]
+        let _active = ActiveHostio::enter(stringify!($func));
+        #[cfg(feature = "experimental")]
+        if Program::current().prepare_hostio(stringify!($func)).is_err() {
+            set_trap();
+            return Default::default();
+        }
+        match UserHost::$func(Program::current(), $($arg),*) {
             Ok(value) => value,
             Err(_) => {
                 set_trap();
                 Default::default()
             }
         }
-    };
+    }};
+}
+
+/// Runs a hostio that calls into the EVM, marking the call as pending for the duration. The mark
+/// is cleared however the hostio returns, including when it's refused before running.
+unsafe fn pending_call<T>(method: EvmApiMethod, hostio: impl FnOnce() -> T) -> T {
+    Program::current().pending_call = Some(method);
+    let result = hostio();
+    Program::current().pending_call = None;
+    result
 }
 
 #[no_mangle]
@@ -40,7 +104,8 @@ pub unsafe extern "C" fn user_host__exit_early(status: u32) {
 
 #[no_mangle]
 pub unsafe extern "C" fn user_host__write_result(ptr: GuestPtr, len: u32) {
-    hostio!(write_result(ptr, len))
+    hostio!(write_result(ptr, len));
+    Program::current().record_outs_len();
 }
 
 #[no_mangle]
@@ -77,7 +142,9 @@ pub unsafe extern "C" fn user_host__call_contract(
     gas: u64,
     ret_len: GuestPtr,
 ) -> u8 {
-    hostio!(call_contract(contract, data, data_len, value, gas, ret_len))
+    pending_call(EvmApiMethod::ContractCall, || {
+        hostio!(call_contract(contract, data, data_len, value, gas, ret_len))
+    })
 }
 
 #[no_mangle]
@@ -88,9 +155,11 @@ pub unsafe extern "C" fn user_host__delegate_call_contract(
     gas: u64,
     ret_len: GuestPtr,
 ) -> u8 {
-    hostio!(delegate_call_contract(
-        contract, data, data_len, gas, ret_len
-    ))
+    pending_call(EvmApiMethod::DelegateCall, || {
+        hostio!(delegate_call_contract(
+            contract, data, data_len, gas, ret_len
+        ))
+    })
 }
 
 #[no_mangle]
@@ -101,7 +170,9 @@ pub unsafe extern "C" fn user_host__static_call_contract(
     gas: u64,
     ret_len: GuestPtr,
 ) -> u8 {
-    hostio!(static_call_contract(contract, data, data_len, gas, ret_len))
+    pending_call(EvmApiMethod::StaticCall, || {
+        hostio!(static_call_contract(contract, data, data_len, gas, ret_len))
+    })
 }
 
 #[no_mangle]
@@ -112,7 +183,9 @@ pub unsafe extern "C" fn user_host__create1(
     contract: GuestPtr,
     revert_len: GuestPtr,
 ) {
-    hostio!(create1(code, code_len, value, contract, revert_len))
+    pending_call(EvmApiMethod::Create1, || {
+        hostio!(create1(code, code_len, value, contract, revert_len))
+    })
 }
 
 #[no_mangle]
@@ -124,7 +197,9 @@ pub unsafe extern "C" fn user_host__create2(
     contract: GuestPtr,
     revert_len: GuestPtr,
 ) {
-    hostio!(create2(code, code_len, value, salt, contract, revert_len))
+    pending_call(EvmApiMethod::Create2, || {
+        hostio!(create2(code, code_len, value, salt, contract, revert_len))
+    })
 }
 
 #[no_mangle]
@@ -268,6 +343,21 @@ pub unsafe extern "C" fn user_host__native_keccak256(input: GuestPtr, len: u32,
     hostio!(native_keccak256(input, len, output))
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn user_host__selfdestruct(beneficiary: GuestPtr) {
+    hostio!(selfdestruct(beneficiary))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn user_host__flush_output() {
+    hostio!(flush_output())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn user_host__mcopy(dest: GuestPtr, src: GuestPtr, len: u32) {
+    hostio!(mcopy(dest, src, len))
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn user_host__tx_gas_price(ptr: GuestPtr) {
     hostio!(tx_gas_price(ptr))
@@ -285,5 +375,17 @@ pub unsafe extern "C" fn user_host__tx_origin(ptr: GuestPtr) {
 
 #[no_mangle]
 pub unsafe extern "C" fn user_host__pay_for_memory_grow(pages: u16) {
-    hostio!(pay_for_memory_grow(pages))
+    #[cfg(feature = "diagnostics")]
+    let _active = ActiveHostio::enter("pay_for_memory_grow");
+    let program = Program::current();
+    #[cfg(feature = "experimental")]
+    if program.prepare_hostio("pay_for_memory_grow").is_err() {
+        return set_trap();
+    }
+    if UserHost::pay_for_memory_grow(program, pages).is_err() {
+        return set_trap();
+    }
+    if pages != 0 {
+        program.notify_grow(pages);
+    }
 }
@@ -31,6 +31,16 @@ impl MeteredMachine for Program {
     }
 }
 
+/// Provides the gas left on the current program's meter, or zero once it's exhausted.
+pub(crate) fn current_gas_left(pricing: PricingParams) -> u64 {
+    unsafe {
+        match user_ink_status() {
+            0 => pricing.ink_to_gas(user_ink_left()),
+            _ => 0,
+        }
+    }
+}
+
 impl GasMeteredMachine for Program {
     fn pricing(&self) -> PricingParams {
         self.config.pricing
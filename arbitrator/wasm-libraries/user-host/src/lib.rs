@@ -1,7 +1,12 @@
 // Copyright 2022-2023, Offchain Labs, Inc.
 // For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
 
+#[cfg(feature = "diagnostics")]
+mod dump;
 mod host;
 mod ink;
 mod link;
 mod program;
+
+#[cfg(test)]
+mod test;
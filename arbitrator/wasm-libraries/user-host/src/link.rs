@@ -1,9 +1,14 @@
 // Copyright 2022-2024, Offchain Labs, Inc.
 // For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
 
-use crate::program::Program;
+use crate::program::{warn, Program};
 use arbutil::{
-    evm::{user::UserOutcomeKind, EvmData},
+    evm::{
+        api::{EvmApiMethod, EVM_API_METHOD_REQ_OFFSET},
+        req::{RequestError, RequestHandler},
+        user::UserOutcomeKind,
+        EvmData,
+    },
     format::DebugBytes,
     heapify, Bytes20, Bytes32,
 };
@@ -130,7 +135,20 @@ pub unsafe extern "C" fn programs__new_program(
 /// `request_id` MUST be last request id returned from start_program or send_response.
 #[no_mangle]
 pub unsafe extern "C" fn programs__get_request(id: u32, len_ptr: GuestPtr) -> u32 {
-    let (req_type, len) = Program::current().request_handler().get_request_meta(id);
+    let request = Program::current().request_handler().get_request_meta(id);
+    let (req_type, len) = match request {
+        Ok((req_type, len)) if known_request_type(req_type) => (req_type, len),
+        Ok((req_type, _)) => {
+            warn(format!(
+                "refusing request {id} of unknown type {req_type:#x}"
+            ));
+            (REFUSED_REQUEST.0 as u32, REFUSED_REQUEST.1.len())
+        }
+        Err(error) => {
+            warn(format!("refusing request {id}: {error}"));
+            (REFUSED_REQUEST.0 as u32, REFUSED_REQUEST.1.len())
+        }
+    };
     if len_ptr != GuestPtr(0) {
         STATIC_MEM.write_u32(len_ptr, len as u32);
     }
@@ -145,8 +163,22 @@ pub unsafe extern "C" fn programs__get_request(id: u32, len_ptr: GuestPtr) -> u3
 /// `data_ptr` MUST point to a buffer of at least the length returned by `get_request`
 #[no_mangle]
 pub unsafe extern "C" fn programs__get_request_data(id: u32, data_ptr: GuestPtr) {
-    let (_, data) = Program::current().request_handler().take_request(id);
-    STATIC_MEM.write_slice(data_ptr, &data);
+    match Program::current().request_handler().take_request(id) {
+        Ok((req_type, data)) if known_request_type(req_type) => {
+            STATIC_MEM.write_slice(data_ptr, &data)
+        }
+        _ => STATIC_MEM.write_slice(data_ptr, &REFUSED_REQUEST.1),
+    }
+}
+
+/// The final status and payload given to the host in place of a request it can't have, which
+/// fails the program with no gas left.
+const REFUSED_REQUEST: (UserOutcomeKind, [u8; 8]) = (UserOutcomeKind::Failure, [0; 8]);
+
+/// Whether the host can make sense of a request of the given type. Types below the offset are
+/// program statuses, which signal that the program is done.
+fn known_request_type(req_type: u32) -> bool {
+    req_type < EVM_API_METHOD_REQ_OFFSET || EvmApiMethod::from_request_status(req_type).is_some()
 }
 
 /// sets response for the next request made
@@ -162,12 +194,16 @@ pub unsafe extern "C" fn programs__set_response(
     raw_data_len: usize,
 ) {
     let program = Program::current();
-    program.request_handler().set_response(
+    let response = program.request_handler().set_response(
         id,
         STATIC_MEM.read_slice(result_ptr, result_len),
         STATIC_MEM.read_slice(raw_data_ptr, raw_data_len),
         gas,
     );
+    // an oversized answer is recorded on the program instead, which fails once done
+    if let Err(error @ RequestError::IdMismatch { .. }) = response {
+        panic!("set_response: {error}");
+    }
 }
 
 // removes the last created program
@@ -197,6 +233,8 @@ pub unsafe extern "C" fn program_internal__set_done(mut status: UserOutcomeKind)
 
     let program = Program::current();
     let module = program.module;
+    let flushed = program.request_handler().flush();
+    let request_error = flushed.err().or(program.request_handler().error());
     let mut outs = program.outs.as_slice();
     let mut ink_left = program_ink_left(module);
 
@@ -217,7 +255,15 @@ pub unsafe extern "C" fn program_internal__set_done(mut status: UserOutcomeKind)
         ink_left = 0;
     }
 
+    // check if a request was refused or buffered work couldn't complete
+    if request_error.is_some() {
+        status = OutOfInk;
+        outs = &[];
+        ink_left = 0;
+    }
+
     let gas_left = program.config.pricing.ink_to_gas(ink_left);
+    program.result = Some((status as u32, gas_left));
 
     let mut output = Vec::with_capacity(8 + outs.len());
     output.extend(gas_left.to_be_bytes());
@@ -249,6 +295,7 @@ pub unsafe extern "C" fn programs__create_evm_data(
     block_gas_limit: u64,
     block_number: u64,
     block_timestamp: u64,
+    block_randomness_ptr: GuestPtr,
     contract_address_ptr: GuestPtr,
     module_hash_ptr: GuestPtr,
     msg_sender_ptr: GuestPtr,
@@ -257,6 +304,7 @@ pub unsafe extern "C" fn programs__create_evm_data(
     tx_origin_ptr: GuestPtr,
     cached: u32,
     reentrant: u32,
+    read_only: u32,
 ) -> u64 {
     let evm_data = EvmData {
         block_basefee: read_bytes32(block_basefee_ptr),
@@ -266,6 +314,7 @@ pub unsafe extern "C" fn programs__create_evm_data(
         block_gas_limit,
         block_number,
         block_timestamp,
+        block_randomness: read_bytes32(block_randomness_ptr),
         contract_address: read_bytes20(contract_address_ptr),
         module_hash: read_bytes32(module_hash_ptr),
         msg_sender: read_bytes20(msg_sender_ptr),
@@ -275,6 +324,7 @@ pub unsafe extern "C" fn programs__create_evm_data(
         reentrant,
         return_data_len: 0,
         tracing: false,
+        read_only: read_only != 0,
     };
     heapify(evm_data) as u64
 }
@@ -1,20 +1,51 @@
 // Copyright 2022-2024, Offchain Labs, Inc.
 // For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
 
+#[cfg(feature = "diagnostics")]
+use crate::dump::{DecodeError, ProgramDump};
+use crate::ink;
+#[cfg(feature = "timings")]
+use arbutil::evm::req::MethodTimings;
 use arbutil::{
+    crypto::{self, HashAlgo},
     evm::{
-        api::{EvmApiMethod, VecReader, EVM_API_METHOD_REQ_OFFSET},
-        req::{EvmApiRequestor, RequestHandler},
+        self,
+        abi::{CalldataReader, OutputSink, OutputWriter},
+        api::{EvmApi, EvmApiMethod, VecReader, EVM_API_METHOD_REQ_OFFSET},
+        blake2f, bn256,
+        req::{EvmApiRequestor, RequestError, RequestHandler, RequestStats},
+        rlp::{self, RlpItem},
+        storage::StorageCache,
         user::UserOutcomeKind,
         EvmData,
     },
-    Color,
+    types::AddressParseError,
+    Bytes20, Bytes32, Color, MemoryBoundsError,
 };
 use caller_env::{static_caller::STATIC_MEM, GuestPtr, MemAccess};
 use core::sync::atomic::{compiler_fence, Ordering};
-use eyre::{eyre, Result};
-use prover::programs::prelude::*;
-use std::fmt::Display;
+use eyre::{bail, Result};
+use fnv::FnvHashMap as HashMap;
+#[cfg(feature = "experimental")]
+use fnv::FnvHashSet as HashSet;
+#[cfg(feature = "experimental")]
+use prover::programs::meter::OutOfInkError;
+use prover::{
+    programs::{config::PricingParams, meter::read_ink, prelude::*},
+    value::FunctionType,
+};
+#[cfg(feature = "fuzzing")]
+use rand::RngCore;
+#[cfg(feature = "fuzzing")]
+use rand_pcg::Pcg32;
+#[cfg(any(feature = "timeouts", feature = "timings"))]
+use std::time::Instant;
+use std::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    fmt::Display,
+    thread::LocalKey,
+};
 use user_host_trait::UserHost;
 use wasmer_types::{Pages, WASM_PAGE_SIZE};
 
@@ -24,13 +55,9 @@ extern "C" {
     fn program_memory_size(module: u32) -> u32;
 }
 
-/// Signifies an out-of-bounds memory access was requested.
-pub(crate) struct MemoryBoundsError;
-
-impl From<MemoryBoundsError> for eyre::ErrReport {
-    fn from(_: MemoryBoundsError) -> Self {
-        eyre!("memory access out of bounds")
-    }
+#[link(wasm_import_module = "forward")]
+extern "C" {
+    pub(crate) fn set_trap();
 }
 
 /// The list of active programs. The current program is always the last.
@@ -44,14 +71,290 @@ impl From<MemoryBoundsError> for eyre::ErrReport {
 #[allow(clippy::vec_box)]
 static mut PROGRAMS: Vec<Box<Program>> = vec![];
 
-static mut LAST_REQUEST_ID: u32 = 0x10000;
+/// The id after which request ids are assigned, sequentially.
+const REQUEST_ID_BASE: u32 = 0x10000;
+
+static mut LAST_REQUEST_ID: u32 = REQUEST_ID_BASE;
+
+/// The stream of each program's pseudo-random number generator, whose state is the fuzz seed.
+#[cfg(feature = "fuzzing")]
+const FUZZ_RNG_STREAM: u64 = 0xa02bdbf7bb3c0a7;
+
+/// Restarts request id assignment so that the next request is given `REQUEST_ID_BASE + 1`.
+/// Ids are otherwise never reused, so this only exists for tests.
+#[cfg(test)]
+pub(crate) unsafe fn reset_request_id_counter() {
+    LAST_REQUEST_ID = REQUEST_ID_BASE;
+}
+
+/// The combined length of the payloads of every unanswered request across the program stack.
+static mut IN_FLIGHT_REQUEST_BYTES: usize = 0;
+
+/// The limits given to each program as it's pushed. See [`Program::set_host_limits`].
+#[cfg(feature = "experimental")]
+static mut HOST_LIMITS: HostLimits = HostLimits::NONE;
+
+type CaptureBuffer = LocalKey<RefCell<Option<Vec<String>>>>;
+
+thread_local! {
+    /// Collects the output of [`UserHost::say`] while a capture of it is alive.
+    static SAY_CAPTURE: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+
+    /// Collects the user host's warnings while a capture of them is alive.
+    static WARNING_CAPTURE: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Redirects lines of output into a buffer until dropped. See [`Program::capture_say`] and
+/// [`Program::capture_warnings`].
+pub(crate) struct LineCapture(&'static CaptureBuffer);
+
+impl LineCapture {
+    fn begin(buffer: &'static CaptureBuffer) -> Self {
+        buffer.with_borrow_mut(|lines| *lines = Some(vec![]));
+        Self(buffer)
+    }
+
+    /// Stops capturing, yielding every line since the capture began.
+    pub fn into_lines(self) -> Vec<String> {
+        self.0
+            .with_borrow_mut(|lines| lines.take().unwrap_or_default())
+    }
+
+    /// Adds a line to the buffer if a capture is alive, returning it otherwise.
+    fn record<D: Display>(buffer: &'static CaptureBuffer, text: D) -> Option<D> {
+        buffer.with_borrow_mut(|lines| match lines {
+            Some(lines) => {
+                lines.push(text.to_string());
+                None
+            }
+            None => Some(text),
+        })
+    }
+}
+
+impl Drop for LineCapture {
+    fn drop(&mut self) {
+        self.0.with_borrow_mut(|lines| *lines = None);
+    }
+}
+
+/// Logs a warning about the host, which is captured rather than printed while
+/// [`Program::capture_warnings`] is in effect.
+pub(crate) fn warn<D: Display>(text: D) {
+    if let Some(text) = LineCapture::record(&WARNING_CAPTURE, text) {
+        println!("{} {text}", "Warning:".yellow());
+    }
+}
+
+/// A snapshot of an active program, for diagnostics. See [`Program::stack_report`].
+#[derive(Clone, Debug)]
+pub(crate) struct FrameInfo {
+    /// WAVM module index.
+    pub module: u32,
+    /// Position in the stack of programs, the outermost being `0`.
+    pub depth: usize,
+    /// Length of the calldata in bytes.
+    pub args_len: usize,
+    /// Length of the output in bytes.
+    pub outs_len: usize,
+    /// Size of the program's memory in pages.
+    pub memory_pages: u32,
+    /// The kind of call the program is awaiting the result of, if any.
+    pub pending_request: Option<EvmApiMethod>,
+}
+
+/// A copy of a region of guest memory, for finding what a sequence of hostios changed.
+/// See [`Program::snapshot_region`].
+#[cfg(feature = "diagnostics")]
+#[derive(Clone, Debug)]
+pub(crate) struct MemorySnapshot {
+    ptr: GuestPtr,
+    data: Vec<u8>,
+}
+
+#[cfg(feature = "diagnostics")]
+impl MemorySnapshot {
+    /// Compares the snapshot to the same region of the program's memory now, returning the
+    /// `(offset, before, after)` of each changed byte, where `offset` is relative to the region.
+    pub fn diff_against(&self, program: &Program) -> Vec<(u32, u8, u8)> {
+        let len = self.data.len() as u32;
+        let now = program
+            .read_slice_clamped(self.ptr, len)
+            .unwrap_or_default();
+        let pairs = self.data.iter().zip(now).enumerate();
+        pairs
+            .filter(|(_, (before, after))| **before != *after)
+            .map(|(offset, (&before, after))| (offset as u32, before, after))
+            .collect()
+    }
+}
 
+/// The logical state of a program, for forking speculative branches. Guest memory lives in the
+/// WAVM module and can't be copied here, so it must be handled separately.
+/// See [`Program::fork_state`].
 #[derive(Clone)]
+pub(crate) struct ProgramState {
+    pub args: Vec<u8>,
+    pub outs: Vec<u8>,
+    pub evm_data: EvmData,
+    pub module: u32,
+    pub config: StylusConfig,
+    pub early_exit: Option<UserOutcomeKind>,
+    /// The storage slots read and written so far, awaiting a flush.
+    pub storage_cache: StorageCache,
+}
+
+/// Signifies a hostio was invoked that the program isn't allowed to call.
+#[cfg(feature = "experimental")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct HostioDenied(pub &'static str);
+
+#[cfg(feature = "experimental")]
+impl std::error::Error for HostioDenied {}
+
+#[cfg(feature = "experimental")]
+impl Display for HostioDenied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "hostio {} denied", self.0)
+    }
+}
+
+/// Limits the host enforces on top of those of the [`StylusConfig`]. Stylus knows nothing of
+/// these, so they're confined to experimenting lest replay diverge from native execution.
+#[cfg(feature = "experimental")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct HostLimits {
+    /// Whether out-of-bounds memory accesses abort execution rather than returning an error.
+    pub trap_on_bounds_error: bool,
+    /// The cumulative request gas after which further requests are refused, `0` meaning no
+    /// deadline.
+    pub gas_deadline: u64,
+    /// The maximum length of output appended by the host, `0` meaning no limit.
+    pub max_output_bytes: u32,
+    /// The maximum length of each request's payload, `0` meaning no limit.
+    pub max_request_bytes: u32,
+    /// The maximum combined length of the payloads of unanswered requests, `0` meaning no limit.
+    pub max_total_request_bytes: u32,
+    /// The most programs that may be active at once, past which execution aborts, `0` meaning no
+    /// ceiling. Unlike the config's `max_depth`, this guards the host rather than the program.
+    pub absolute_max_programs: u32,
+    /// The maximum number of requests each program may make, `0` meaning no limit.
+    pub max_requests: u32,
+    /// Whether pushing a program identical to the current one, a sign of runaway recursion in the
+    /// host, aborts execution.
+    pub detect_self_recursion: bool,
+    /// The longest answer, counting its result and raw data, a request may receive, `0` meaning
+    /// no limit.
+    pub max_response_bytes: u32,
+}
+
+#[cfg(feature = "experimental")]
+impl HostLimits {
+    /// Enforces nothing beyond the config.
+    pub const NONE: Self = Self {
+        trap_on_bounds_error: false,
+        gas_deadline: 0,
+        max_output_bytes: 0,
+        max_request_bytes: 0,
+        max_total_request_bytes: 0,
+        absolute_max_programs: 0,
+        max_requests: 0,
+        detect_self_recursion: false,
+        max_response_bytes: 0,
+    };
+}
+
+#[cfg(feature = "experimental")]
+impl Default for HostLimits {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// A function exported by a program's module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ExportInfo {
+    pub name: String,
+    pub ty: FunctionType,
+}
+
+/// Lists the functions a module exports. The replay machine offers no such introspection, so
+/// host tooling that wants it must supply a backend. See [`Program::set_export_backend`].
+pub(crate) type ExportBackend = Box<dyn Fn(u32) -> Result<Vec<ExportInfo>>>;
+
+/// Receives the name, arguments, and outputs of each traced hostio.
+pub(crate) type TraceSink = Box<dyn FnMut(ProgramView<'_>, &str, &[u8], &[u8])>;
+
+/// A read-only view of a program, for callbacks that inspect but mustn't modify its state.
+#[derive(Clone, Copy)]
+pub(crate) struct ProgramView<'a>(&'a Program);
+
+impl ProgramView<'_> {
+    /// WAVM module index.
+    pub fn module(&self) -> u32 {
+        self.0.module
+    }
+
+    /// Position in the stack of programs, the outermost being `0`.
+    pub fn depth(&self) -> usize {
+        let mut programs = unsafe { PROGRAMS.iter() };
+        let depth = programs.position(|program| std::ptr::eq(&**program, self.0));
+        depth.expect("program not active")
+    }
+
+    /// Arguments passed via the VM.
+    pub fn args(&self) -> &[u8] {
+        &self.0.args
+    }
+
+    /// Output generated so far.
+    pub fn output(&self) -> &[u8] {
+        &self.0.outs
+    }
+
+    /// EVM Context info.
+    pub fn evm_data(&self) -> &EvmData {
+        &self.0.evm_data
+    }
+}
+
 pub(crate) struct UserHostRequester {
     data: Option<Vec<u8>>,
     answer: Option<(Vec<u8>, VecReader, u64)>,
     req_type: u32,
     id: u32,
+    /// The gas below which requests are refused.
+    min_gas_reserve: u64,
+    /// Converts the ink left on the meter to gas.
+    pricing: PricingParams,
+    /// The gas consumed across all requests.
+    gas_used: u64,
+    /// The cumulative request gas after which requests are refused.
+    gas_deadline: u64,
+    /// The longest payload a request may carry.
+    max_request_len: usize,
+    /// The most bytes unanswered requests across the program stack may hold at once.
+    max_total_request_len: usize,
+    /// The longest answer a request may receive.
+    max_response_len: usize,
+    /// The number of requests made.
+    requests_made: u32,
+    /// The most requests that may be made.
+    max_requests: u32,
+    /// The requests made since the stats were last taken, by method.
+    stats: RequestStats,
+    /// The wall-clock time after which requests are refused.
+    #[cfg(feature = "timeouts")]
+    deadline: Option<Instant>,
+    /// Why a request was refused, if one was.
+    error: Option<RequestError>,
+    /// Whether a request has been sent but not yet answered.
+    awaiting_response: bool,
+    /// Called with the method and payload of every request before it's sent.
+    interceptor: Option<Box<dyn FnMut(EvmApiMethod, &[u8]) + Send>>,
+    /// The wall-clock time spent awaiting the host, by method.
+    #[cfg(feature = "timings")]
+    timings: MethodTimings,
 }
 
 impl UserHostRequester {
@@ -61,8 +364,99 @@ impl UserHostRequester {
             data: None,
             answer: None,
             id: 0,
+            min_gas_reserve: 0,
+            pricing: PricingParams::default(),
+            gas_used: 0,
+            gas_deadline: 0,
+            max_request_len: 0,
+            max_total_request_len: 0,
+            max_response_len: 0,
+            requests_made: 0,
+            max_requests: 0,
+            stats: RequestStats::default(),
+            #[cfg(feature = "timeouts")]
+            deadline: None,
+            error: None,
+            awaiting_response: false,
+            interceptor: None,
+            #[cfg(feature = "timings")]
+            timings: MethodTimings::default(),
         }
     }
+
+    /// Creates a requester enforcing the limits of the given config, and of the host's if
+    /// experimenting.
+    fn new(config: &StylusConfig) -> Self {
+        let mut requester = Self::default();
+        requester.min_gas_reserve = config.min_gas_reserve;
+        requester.pricing = config.pricing;
+        #[cfg(feature = "experimental")]
+        {
+            let limits = unsafe { HOST_LIMITS };
+            requester.gas_deadline = limits.gas_deadline;
+            requester.max_request_len = limits.max_request_bytes as usize;
+            requester.max_total_request_len = limits.max_total_request_bytes as usize;
+            requester.max_requests = limits.max_requests;
+            requester.max_response_len = limits.max_response_bytes as usize;
+        }
+        requester
+    }
+
+    /// Provides the gas consumed across all requests.
+    pub fn gas_used(&self) -> u64 {
+        self.gas_used
+    }
+
+    /// Provides the reason a request was refused, if one was.
+    pub fn error(&self) -> Option<RequestError> {
+        self.error
+    }
+
+    /// Sets the callback invoked with the method and payload of every request before it's sent,
+    /// replacing any prior one. Useful for tracing and mocking.
+    pub fn set_request_interceptor(
+        &mut self,
+        interceptor: Box<dyn FnMut(EvmApiMethod, &[u8]) + Send>,
+    ) {
+        self.interceptor = Some(interceptor);
+    }
+
+    /// Provides the requests made since the stats were last taken, by method, and zeroes them.
+    /// The request limit still counts every request made.
+    pub fn take_request_stats(&mut self) -> RequestStats {
+        std::mem::take(&mut self.stats)
+    }
+
+    /// Provides the wall-clock time spent awaiting the host, by method. Only available outside of
+    /// proving.
+    #[cfg(feature = "timings")]
+    pub fn method_timings(&self) -> &MethodTimings {
+        &self.timings
+    }
+
+    /// Whether a request has been sent but not yet answered, during which guest memory mustn't
+    /// be written.
+    pub fn awaiting_response(&self) -> bool {
+        self.awaiting_response
+    }
+
+    /// Whether an answer is held, awaiting the program's return from the request.
+    #[cfg(test)]
+    pub fn holds_answer(&self) -> bool {
+        self.answer.is_some()
+    }
+
+    /// Provides the type and payload of the outstanding request, if it's yet to be answered.
+    pub fn pending_request(&self) -> Option<(u32, &[u8])> {
+        let data = self.data.as_ref()?;
+        Some((self.req_type, data))
+    }
+}
+
+impl Drop for UserHostRequester {
+    fn drop(&mut self) {
+        unsafe { self.abandon_request() };
+    }
 }
 
 /// An active user program.
@@ -71,6 +465,10 @@ pub(crate) struct Program {
     pub args: Vec<u8>,
     /// Output generated by the program.
     pub outs: Vec<u8>,
+    /// The largest the output has ever been, even if since truncated.
+    outs_high_water: usize,
+    /// Whether an append to the output was cut short by the output limit.
+    output_truncated: bool,
     /// Mechanism for calling back into Geth.
     pub evm_api: EvmApiRequestor<VecReader, UserHostRequester>,
     /// EVM Context info.
@@ -81,6 +479,30 @@ pub(crate) struct Program {
     pub config: StylusConfig,
     /// Whether the program exited early.
     pub early_exit: Option<UserOutcomeKind>,
+    /// The final status and gas left, once the program is done.
+    pub result: Option<(u32, u64)>,
+    /// The kind of call the program is awaiting the result of, if any.
+    pub pending_call: Option<EvmApiMethod>,
+    /// Called with the old and new page counts whenever the program grows its memory.
+    grow_hook: Option<Box<dyn FnMut(u32, u32)>>,
+    /// Called with each traced hostio.
+    trace_sink: Option<TraceSink>,
+    /// Lists the exports of a module, given its index.
+    export_backend: Option<ExportBackend>,
+    /// Context attached by host tooling, keyed by type. Never affects execution.
+    extensions: HashMap<TypeId, Box<dyn Any>>,
+    /// Extra ink charged by each named hostio, for experimenting with the cost model.
+    #[cfg(feature = "experimental")]
+    hostio_surcharges: HashMap<&'static str, u64>,
+    /// The hostios the program may call, if restricted.
+    #[cfg(feature = "experimental")]
+    allowed_hostios: Option<HashSet<&'static str>>,
+    /// The limits in effect when the program was pushed.
+    #[cfg(feature = "experimental")]
+    limits: HostLimits,
+    /// A pseudo-random source for fuzzing harnesses, seeded by the config.
+    #[cfg(feature = "fuzzing")]
+    rng: Pcg32,
 }
 
 #[link(wasm_import_module = "hostio")]
@@ -96,51 +518,97 @@ impl UserHostRequester {
         result: Vec<u8>,
         raw_data: Vec<u8>,
         gas: u64,
-    ) {
-        self.answer = Some((result, VecReader::new(raw_data), gas));
-        if req_id != self.id {
-            panic!("bad req id returning from send_request")
+    ) -> Result<(), RequestError> {
+        RequestError::check_id(self.id, req_id)?;
+        self.set_pending(None);
+        self.awaiting_response = false;
+
+        // an oversized answer is dropped, failing the program once it resumes
+        let len = result.len().saturating_add(raw_data.len());
+        if let Err(error) = RequestError::check_response_len(len, self.max_response_len) {
+            self.error = Some(error);
+            return Err(error);
         }
+        self.answer = Some((result, VecReader::new(raw_data), gas));
+        self.gas_used = self.gas_used.saturating_add(gas);
         compiler_fence(Ordering::SeqCst);
+        Ok(())
     }
 
     pub unsafe fn set_request(&mut self, req_type: u32, data: &[u8]) -> u32 {
         LAST_REQUEST_ID += 1;
         self.id = LAST_REQUEST_ID;
         self.req_type = req_type;
-        self.data = Some(data.to_vec());
+        self.set_pending(Some(data.to_vec()));
         self.answer = None;
         self.id
     }
 
-    pub unsafe fn get_request_meta(&self, id: u32) -> (u32, usize) {
-        if self.id != id {
-            panic!("get_request got wrong id");
+    /// Discards the outstanding request. A request never answered suggests the host bridge lost
+    /// track of it, and so is warned about, unlike the final status of a program that's done.
+    unsafe fn abandon_request(&mut self) {
+        if let Some((req_type, data)) = self.pending_request() {
+            if let Some(method) = EvmApiMethod::from_request_status(req_type) {
+                let len = data.len();
+                warn(format!(
+                    "dropped with unanswered request {method:?} of {len} bytes"
+                ));
+            }
         }
+        self.set_pending(None);
+    }
+
+    /// Replaces the unanswered request's payload, keeping the in-flight total up to date.
+    unsafe fn set_pending(&mut self, data: Option<Vec<u8>>) {
+        let old = self.data.as_ref().map_or(0, Vec::len);
+        let new = data.as_ref().map_or(0, Vec::len);
+        IN_FLIGHT_REQUEST_BYTES = IN_FLIGHT_REQUEST_BYTES.saturating_sub(old) + new;
+        self.data = data;
+    }
+
+    pub unsafe fn get_request_meta(&self, id: u32) -> Result<(u32, usize), RequestError> {
+        RequestError::check_id(self.id, id)?;
         let size = self.data.as_ref().expect("no data get_request_meta").len();
-        (self.req_type, size)
+        Ok((self.req_type, size))
     }
 
-    pub unsafe fn take_request(&mut self, id: u32) -> (u32, Vec<u8>) {
-        if self.id != id {
-            panic!("get_request got wrong id");
-        }
-        let data = self.data.take().expect("no request on take_request");
-        (self.req_type, data)
+    pub unsafe fn take_request(&mut self, id: u32) -> Result<(u32, Vec<u8>), RequestError> {
+        RequestError::check_id(self.id, id)?;
+        // the data is kept until answered so that the request can be inspected while in flight
+        let data = self.data.clone().expect("no request on take_request");
+        Ok((self.req_type, data))
     }
 
     #[no_mangle]
-    unsafe fn send_request(&mut self, req_type: u32, data: Vec<u8>) -> (Vec<u8>, VecReader, u64) {
+    unsafe fn send_request(
+        &mut self,
+        req_type: u32,
+        data: Vec<u8>,
+    ) -> Result<(Vec<u8>, VecReader, u64), RequestError> {
+        let gas_left = ink::current_gas_left(self.pricing);
+        RequestError::check_gas_reserve(gas_left, self.min_gas_reserve)?;
+        RequestError::check_deadline(self.gas_used, self.gas_deadline)?;
+        RequestError::check_payload_len(data.len(), self.max_request_len)?;
+        let (in_flight, budget) = (IN_FLIGHT_REQUEST_BYTES, self.max_total_request_len);
+        RequestError::check_request_budget(in_flight, data.len(), budget)?;
+        RequestError::check_request_count(self.requests_made, self.max_requests)?;
+        #[cfg(feature = "timeouts")]
+        RequestError::check_timeout(Instant::now(), self.deadline)?;
+        self.requests_made += 1;
+
         let req_id = self.set_request(req_type, &data);
+        self.awaiting_response = true;
         compiler_fence(Ordering::SeqCst);
 
         let got_id = program_request(req_id);
         compiler_fence(Ordering::SeqCst);
 
-        if got_id != req_id {
-            panic!("bad req id returning from send_request")
-        }
-        self.answer.take().unwrap()
+        RequestError::check_id(req_id, got_id)?;
+        let Some(answer) = self.answer.take() else {
+            return Err(self.error.expect("answer rejected without an error"));
+        };
+        RequestError::check_gas_cost(answer.2)?;
+        Ok(answer)
     }
 }
 
@@ -150,42 +618,386 @@ impl RequestHandler<VecReader> for UserHostRequester {
         req_type: EvmApiMethod,
         req_data: impl AsRef<[u8]>,
     ) -> (Vec<u8>, VecReader, u64) {
-        unsafe {
-            self.send_request(
-                req_type as u32 + EVM_API_METHOD_REQ_OFFSET,
-                req_data.as_ref().to_vec(),
-            )
+        if let Some(interceptor) = &mut self.interceptor {
+            interceptor(req_type, req_data.as_ref());
+        }
+        let status = req_type as u32 + EVM_API_METHOD_REQ_OFFSET;
+        self.stats.record(req_type);
+
+        #[cfg(feature = "timings")]
+        let start = Instant::now();
+        let result = unsafe { self.send_request(status, req_data.as_ref().to_vec()) };
+        #[cfg(feature = "timings")]
+        self.timings.record(req_type, start.elapsed());
+
+        match result {
+            Ok(answer) => answer,
+            Err(error) => {
+                // the hostio will trap on return, so any zeroed answer that parses will do
+                self.error = Some(error);
+                unsafe { set_trap() };
+                (vec![0; 32], VecReader::new(vec![]), 0)
+            }
         }
     }
 }
 
 impl Program {
+    /// Snapshots the program's state, excluding its memory, for post-mortem debugging.
+    #[cfg(feature = "diagnostics")]
+    pub fn dump(&self) -> Vec<u8> {
+        let pending = self.evm_api.handler().pending_request();
+        let dump = ProgramDump {
+            args: self.args.clone(),
+            outs: self.outs.clone(),
+            evm_data: self.evm_data,
+            module: self.module,
+            config: self.config,
+            pending_request: pending.map(|(req_type, data)| (req_type, data.to_vec())),
+        };
+        dump.encode()
+    }
+
+    /// Decodes a snapshot taken by [`Program::dump`]. The result can be inspected but not run.
+    #[cfg(feature = "diagnostics")]
+    pub fn load(data: &[u8]) -> Result<ProgramDump, DecodeError> {
+        ProgramDump::decode(data)
+    }
+
+    /// Copies everything but the program's memory, sharing nothing mutable with the original.
+    pub fn fork_state(&self) -> ProgramState {
+        ProgramState {
+            args: self.args.clone(),
+            outs: self.outs.clone(),
+            evm_data: self.evm_data,
+            module: self.module,
+            config: self.config,
+            early_exit: self.early_exit,
+            storage_cache: self.evm_api.storage_cache().clone(),
+        }
+    }
+
+    /// Provides the key and value of each storage slot written but not yet flushed, sorted by key.
+    pub fn dirty_slots(&self) -> Vec<(Bytes32, Bytes32)> {
+        self.evm_api.storage_cache().dirty_slots()
+    }
+
     /// Adds a new program, making it current.
     pub fn push_new(args: Vec<u8>, evm_data: EvmData, module: u32, config: StylusConfig) {
+        #[cfg(feature = "experimental")]
+        Self::check_push_limits(&args, module);
+
         let program = Self {
             args,
             outs: vec![],
-            evm_api: EvmApiRequestor::new(UserHostRequester::default()),
+            outs_high_water: 0,
+            output_truncated: false,
+            evm_api: EvmApiRequestor::new(UserHostRequester::new(&config)),
             evm_data,
             module,
             config,
             early_exit: None,
+            result: None,
+            pending_call: None,
+            grow_hook: None,
+            trace_sink: None,
+            export_backend: None,
+            extensions: HashMap::default(),
+            #[cfg(feature = "experimental")]
+            hostio_surcharges: HashMap::default(),
+            #[cfg(feature = "experimental")]
+            allowed_hostios: None,
+            #[cfg(feature = "experimental")]
+            limits: unsafe { HOST_LIMITS },
+            #[cfg(feature = "fuzzing")]
+            rng: Pcg32::new(config.fuzz_seed, FUZZ_RNG_STREAM),
         };
         unsafe { PROGRAMS.push(Box::new(program)) }
     }
 
+    /// Aborts should another program exceed the host's limits. These are safety ceilings rather
+    /// than recoverable errors, as exceeding them risks the host's stack.
+    #[cfg(feature = "experimental")]
+    fn check_push_limits(args: &[u8], module: u32) {
+        let limits = unsafe { HOST_LIMITS };
+        let ceiling = limits.absolute_max_programs as usize;
+        if ceiling != 0 && Self::depth() >= ceiling {
+            panic!("more than {ceiling} programs active at once");
+        }
+
+        let caller = unsafe { PROGRAMS.last() };
+        if limits.detect_self_recursion {
+            if let Some(caller) = caller.filter(|x| x.module == module && x.args == args) {
+                panic!("program for module {} pushed atop itself", caller.module);
+            }
+        }
+    }
+
+    /// Adds a new program, making it current, and returns its index in the stack of programs.
+    pub fn push_new_indexed(
+        args: Vec<u8>,
+        evm_data: EvmData,
+        module: u32,
+        config: StylusConfig,
+    ) -> usize {
+        Self::push_new(args, evm_data, module, config);
+        Self::depth() - 1
+    }
+
+    /// Prepares the program to run again with new inputs, reusing its output buffer rather than
+    /// reallocating it. The grow hook and extensions are kept.
+    pub fn reset(&mut self, args: Vec<u8>, evm_data: EvmData, module: u32, config: StylusConfig) {
+        unsafe { self.request_handler().abandon_request() };
+        self.args = args;
+        self.outs.clear();
+        self.outs_high_water = 0;
+        self.output_truncated = false;
+        self.evm_api = EvmApiRequestor::new(UserHostRequester::new(&config));
+        self.evm_data = evm_data;
+        self.module = module;
+        self.config = config;
+        self.early_exit = None;
+        self.result = None;
+        self.pending_call = None;
+        #[cfg(feature = "experimental")]
+        {
+            self.limits = unsafe { HOST_LIMITS };
+        }
+        #[cfg(feature = "fuzzing")]
+        {
+            self.rng = Pcg32::new(config.fuzz_seed, FUZZ_RNG_STREAM);
+        }
+    }
+
     /// Removes the current program
     pub fn pop() {
+        Self::take();
+    }
+
+    /// Removes the current program, returning it alongside the status and gas left it finished
+    /// with. The program must be done.
+    pub fn pop_with_result() -> (Box<Self>, u32, u64) {
+        let program = Self::take();
+        let (status, gas_left) = program.result.expect("program not done");
+        (program, status, gas_left)
+    }
+
+    fn take() -> Box<Self> {
         unsafe {
-            PROGRAMS.pop().expect("no program");
+            let mut program = PROGRAMS.pop().expect("no program");
+            program.request_handler().abandon_request();
+            program
         }
     }
 
+    /// Removes all programs at once.
+    ///
+    /// This must only be called between top-level executions, when no program is running.
+    pub fn clear_all() {
+        unsafe {
+            PROGRAMS.clear();
+            IN_FLIGHT_REQUEST_BYTES = 0;
+        }
+    }
+
+    /// Provides the number of active programs.
+    pub fn depth() -> usize {
+        unsafe { PROGRAMS.len() }
+    }
+
+    /// Provides how many more programs may be nested before reaching
+    /// [`StylusConfig::max_call_depth`], or `0` if at or over it.
+    pub fn depth_remaining(&self) -> usize {
+        let max_call_depth = self.config.max_call_depth as usize;
+        max_call_depth.saturating_sub(Self::depth())
+    }
+
+    /// Collects the output of [`UserHost::say`] rather than printing it, until the returned guard
+    /// is dropped. Useful in tests.
+    pub fn capture_say() -> LineCapture {
+        LineCapture::begin(&SAY_CAPTURE)
+    }
+
+    /// Collects the user host's warnings rather than printing them, until the returned guard is
+    /// dropped. Useful in tests.
+    pub fn capture_warnings() -> LineCapture {
+        LineCapture::begin(&WARNING_CAPTURE)
+    }
+
+    /// Provides the module and gas used of every active program, outermost first. A frame's gas is
+    /// what its answered requests cost, so a call still underway isn't counted until it returns,
+    /// at which point it includes the gas of the frames it ran.
+    pub fn gas_by_frame() -> Vec<(u32, u64)> {
+        let programs = unsafe { PROGRAMS.iter() };
+        let frames = programs.map(|program| (program.module, program.evm_api.handler().gas_used()));
+        frames.collect()
+    }
+
+    /// Snapshots every active program, outermost first, for logging when something goes wrong.
+    pub fn stack_report() -> Vec<FrameInfo> {
+        let programs = unsafe { PROGRAMS.iter() };
+        programs
+            .enumerate()
+            .map(|(depth, program)| FrameInfo {
+                module: program.module,
+                depth,
+                args_len: program.args.len(),
+                outs_len: program.outs.len(),
+                memory_pages: program.memory_size().0,
+                pending_request: program.pending_call,
+            })
+            .collect()
+    }
+
+    /// Lists the unanswered request of each frame that has one, as `(frame_index, req_type, data)`.
+    pub fn pending_requests() -> Vec<(usize, u32, Vec<u8>)> {
+        let programs = unsafe { PROGRAMS.iter() };
+        programs
+            .enumerate()
+            .filter_map(|(index, program)| {
+                let (req_type, data) = program.evm_api.handler().pending_request()?;
+                Some((index, req_type, data.to_vec()))
+            })
+            .collect()
+    }
+
+    /// Whether the program executes in a static context, where state may not be modified. The
+    /// host decides this, as programs run beneath a static call inherit it from their caller.
+    pub fn is_static(&self) -> bool {
+        self.evm_data.read_only
+    }
+
     /// Provides a reference to the current program.
     pub fn current() -> &'static mut Self {
         unsafe { PROGRAMS.last_mut().expect("no program") }
     }
 
+    /// Provides a reference to the program at the given index in the stack of programs.
+    pub fn get(index: usize) -> Option<&'static mut Self> {
+        unsafe { PROGRAMS.get_mut(index).map(|x| &mut **x) }
+    }
+
+    /// Charges `ink` on top of the usual cost whenever the named hostio is invoked, replacing any
+    /// prior surcharge. A surcharge of `0` removes it. Stylus has no such notion, so this is
+    /// only available when experimenting.
+    #[cfg(feature = "experimental")]
+    pub fn set_hostio_surcharge(&mut self, hostio: &'static str, ink: u64) {
+        match ink {
+            0 => self.hostio_surcharges.remove(hostio),
+            _ => self.hostio_surcharges.insert(hostio, ink),
+        };
+    }
+
+    /// Sets the wall-clock time after which the program's requests are refused, as a backstop for
+    /// slow host bridges. Only available outside of proving.
+    #[cfg(feature = "timeouts")]
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        self.request_handler().deadline = Some(deadline);
+    }
+
+    /// Provides the gas price of the transaction, as seen by the `tx_gas_price` hostio.
+    pub fn gas_price(&self) -> Bytes32 {
+        self.evm_data.tx_gas_price
+    }
+
+    /// Changes the gas price the program sees from here on, for exercising fee-sensitive logic
+    /// in tests and simulations. Proving always uses the price the transaction was made with, so
+    /// this mustn't be called there.
+    pub fn set_gas_price(&mut self, price: Bytes32) {
+        self.evm_data.tx_gas_price = price;
+    }
+
+    /// Sets the limits enforced on programs pushed or reset from here on, beyond those of their
+    /// configs. Stylus enforces no such limits, so this is only available when experimenting.
+    #[cfg(feature = "experimental")]
+    pub fn set_host_limits(limits: HostLimits) {
+        unsafe { HOST_LIMITS = limits };
+    }
+
+    /// Restricts the program to calling only the given hostios, or lifts any restriction if `None`.
+    /// Stylus can't restrict hostios, so this is only available when experimenting.
+    #[cfg(feature = "experimental")]
+    pub fn set_allowed_hostios(&mut self, allowed: Option<HashSet<&'static str>>) {
+        self.allowed_hostios = allowed;
+    }
+
+    /// Ensures the named hostio may be called, then buys its surcharge, if any.
+    #[cfg(feature = "experimental")]
+    pub fn prepare_hostio(&mut self, hostio: &'static str) -> Result<()> {
+        if let Some(allowed) = &self.allowed_hostios {
+            if !allowed.contains(hostio) {
+                return Err(HostioDenied(hostio).into());
+            }
+        }
+        self.buy_hostio_surcharge(hostio)?;
+        Ok(())
+    }
+
+    /// Buys the surcharge configured for the named hostio, if any.
+    #[cfg(feature = "experimental")]
+    pub fn buy_hostio_surcharge(&mut self, hostio: &str) -> Result<(), OutOfInkError> {
+        match self.hostio_surcharges.get(hostio) {
+            Some(&ink) => self.buy_ink(ink),
+            None => Ok(()),
+        }
+    }
+
+    /// Sets the callback invoked with the old and new page counts whenever the program grows its
+    /// memory, replacing any prior one.
+    pub fn set_grow_hook(&mut self, hook: Box<dyn FnMut(u32, u32)>) {
+        self.grow_hook = Some(hook);
+    }
+
+    /// Sets the callback invoked with each traced hostio, replacing any prior one. Hostios are
+    /// traced only when [`EvmData::tracing`] is set.
+    pub fn set_trace_sink(&mut self, sink: TraceSink) {
+        self.trace_sink = Some(sink);
+    }
+
+    /// Sets the backend that lists the functions the program's module exports, replacing any
+    /// prior one.
+    pub fn set_export_backend(&mut self, backend: ExportBackend) {
+        self.export_backend = Some(backend);
+    }
+
+    /// Lists the functions the program's module exports, as reported by the export backend.
+    pub fn exports(&self) -> Result<Vec<ExportInfo>> {
+        let Some(backend) = &self.export_backend else {
+            bail!("no export backend for module {}", self.module);
+        };
+        backend(self.module)
+    }
+
+    /// Provides a read-only view of the program.
+    pub fn view(&self) -> ProgramView<'_> {
+        ProgramView(self)
+    }
+
+    /// Attaches a value for host tooling to retrieve later, replacing any prior value of its type.
+    pub fn set_extension<T: 'static>(&mut self, value: T) {
+        self.extensions.insert(TypeId::of::<T>(), Box::new(value));
+    }
+
+    /// Retrieves the value of the given type attached via [`Program::set_extension`], if any.
+    pub fn get_extension<T: 'static>(&self) -> Option<&T> {
+        self.extensions.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+
+    /// Informs the grow hook, if any, that the program is growing its memory by `pages`.
+    pub fn notify_grow(&mut self, pages: u16) {
+        let old_pages = self.memory_size().0;
+        if let Some(hook) = &mut self.grow_hook {
+            hook(old_pages, old_pages.saturating_add(pages.into()));
+        }
+    }
+
+    /// Provides the program's pseudo-random number generator. Programs with the same
+    /// [`StylusConfig::fuzz_seed`] produce the same sequence.
+    #[cfg(feature = "fuzzing")]
+    pub fn rng(&mut self) -> &mut impl RngCore {
+        &mut self.rng
+    }
+
     /// Reads the program's memory size in pages.
     fn memory_size(&self) -> Pages {
         unsafe { Pages(program_memory_size(self.module)) }
@@ -196,23 +1008,471 @@ impl Program {
         self.memory_size().0 as u64 * WASM_PAGE_SIZE as u64
     }
 
+    /// Provides the block's basefee in wei, as needed for EIP-1559 fee math.
+    pub fn base_fee(&self) -> Bytes32 {
+        self.evm_data.block_basefee
+    }
+
+    /// Provides the chain id, which is constant for the execution and so never requires a request.
+    pub fn chain_id(&self) -> u64 {
+        self.evm_data.chainid
+    }
+
+    /// Provides the price of ink, measured in bips of an evm gas.
+    pub fn ink_price(&self) -> u64 {
+        self.config.pricing.ink_price.into()
+    }
+
+    /// Provides the address of the executing contract. Delegate calls execute in the context of
+    /// their caller, so for a proxy this is the proxy's address rather than the implementation's.
+    pub fn address(&self) -> Bytes20 {
+        self.evm_data.contract_address
+    }
+
+    /// Provides the address that called the current program. Delegate calls preserve the
+    /// caller's sender, so the original sender is seen through any number of them.
+    pub fn sender(&self) -> Bytes20 {
+        self.evm_data.msg_sender
+    }
+
+    /// Provides the wei sent with the current call. Delegate calls inherit their caller's value.
+    pub fn call_value(&self) -> Bytes32 {
+        self.evm_data.msg_value
+    }
+
+    /// Provides the block's randomness, which post-merge is the `PREVRANDAO` value of the beacon chain.
+    /// Note that this value is known ahead of time by validators and is not a secure source of randomness.
+    pub fn block_randomness(&self) -> Bytes32 {
+        self.evm_data.block_randomness
+    }
+
+    /// Appends to the program's output, tracking its high-water mark. When experimenting, data
+    /// past the host's output limit is dropped, which is recorded so that the host may decide
+    /// to revert.
+    pub fn append_outs(&mut self, data: &[u8]) {
+        #[cfg(feature = "experimental")]
+        let data = self.clamp_to_output_limit(data);
+        self.outs.extend_from_slice(data);
+        self.record_outs_len();
+    }
+
+    /// Cuts the data short of the host's output limit, recording whether any had to be dropped.
+    #[cfg(feature = "experimental")]
+    fn clamp_to_output_limit<'a>(&mut self, data: &'a [u8]) -> &'a [u8] {
+        let limit = self.limits.max_output_bytes as u64;
+        if limit == 0 {
+            return data;
+        }
+        let len = self.outs.len() as u64;
+        let fits = arbutil::clamp_access(len, data.len() as u64, limit).unwrap_or_default();
+        if fits < data.len() as u64 {
+            self.output_truncated = true;
+            return &data[..fits as usize];
+        }
+        data
+    }
+
+    /// Aborts the program, reverting with the given data, which is subject to the output limit.
+    /// As with the `exit_early` hostio, the status is applied when the program is done.
+    pub fn revert(&mut self, data: &[u8]) -> Result<()> {
+        self.pay_for_geth_bytes(data.len().try_into()?)?;
+        self.outs.clear();
+        self.append_outs(data);
+        self.early_exit = Some(UserOutcomeKind::Revert);
+        unsafe { set_trap() };
+        Ok(())
+    }
+
+    /// Whether an append to the output was cut short by the output limit.
+    pub fn output_truncated(&self) -> bool {
+        self.output_truncated
+    }
+
+    /// Updates the output's high-water mark after the output changes.
+    pub fn record_outs_len(&mut self) {
+        self.outs_high_water = self.outs_high_water.max(self.outs.len());
+    }
+
+    /// Provides the largest the output has ever been, which is useful in sizing future buffers.
+    pub fn outs_high_water(&self) -> usize {
+        self.outs_high_water
+    }
+
+    /// Provides a reader for decoding the program's ABI-encoded calldata.
+    pub fn calldata_reader(&self) -> CalldataReader<'_> {
+        CalldataReader::new(&self.args)
+    }
+
+    /// Provides the calldata's function selector, if it's long enough to have one.
+    pub fn selector(&self) -> Option<[u8; 4]> {
+        self.calldata_reader().selector().ok()
+    }
+
+    /// Checks that the output is a valid ABI encoding of the given tuple signature, like `(address,bytes)`.
+    #[cfg(feature = "diagnostics")]
+    pub fn validate_output_abi(&self, signature: &str) -> Result<()> {
+        evm::abi::validate(signature, &self.outs)
+    }
+
+    /// Computes the intrinsic gas the calldata would cost a transaction, for gas estimation.
+    pub fn calldata_intrinsic_gas(&self) -> u64 {
+        evm::calldata_gas(&self.args)
+    }
+
+    /// Provides a writer that ABI-encodes values as a tuple, appending it to the program's output
+    /// via [`Program::append_outs`] once finished so that the output limit applies.
+    pub fn output_writer(&mut self) -> OutputWriter<&mut Self> {
+        OutputWriter::new(self)
+    }
+
+    /// ABI-encodes the values the closure writes as a tuple, appending it to the program's output.
+    /// See [`Program::output_writer`].
+    pub fn write_output_abi(&mut self, encode: impl FnOnce(&mut OutputWriter<&mut Self>)) {
+        let mut writer = self.output_writer();
+        encode(&mut writer);
+        writer.finish();
+    }
+
+    /// Provides the output generated so far.
+    pub fn output(&self) -> &[u8] {
+        &self.outs
+    }
+
+    /// Provides the length of the output generated so far in bytes.
+    pub fn output_len(&self) -> usize {
+        self.outs.len()
+    }
+
     /// Provides the length of the program's calldata in bytes.
     pub fn args_len(&self) -> usize {
         self.args.len()
     }
 
-    /// Ensures an access is within bounds
+    /// Estimates the ink needed to read `len` bytes, without buying any.
+    pub fn estimate_memory_ink(&self, len: u32) -> u64 {
+        read_ink(len)
+    }
+
+    /// Ensures an access is within bounds. If the host's limits say so, an access that isn't traps
+    /// the program even should the caller recover from the error.
     fn check_memory_access(&self, ptr: GuestPtr, bytes: u32) -> Result<(), MemoryBoundsError> {
         let end = ptr.to_u64() + bytes as u64;
         if end > self.memory_size_bytes() {
+            #[cfg(feature = "experimental")]
+            if self.limits.trap_on_bounds_error {
+                unsafe { set_trap() };
+            }
             return Err(MemoryBoundsError);
         }
         Ok(())
     }
 
+    /// Writing to memory while awaiting a response is a protocol violation.
+    fn check_no_request_in_flight(&self) {
+        let awaiting = self.evm_api.handler().awaiting_response();
+        debug_assert!(!awaiting, "memory written while a request is in flight");
+    }
+
+    /// Reads up to `len` bytes, stopping early at the end of memory. Only errors if `ptr` itself
+    /// is out of bounds.
+    pub fn read_slice_clamped(
+        &self,
+        ptr: GuestPtr,
+        len: u32,
+    ) -> Result<Vec<u8>, MemoryBoundsError> {
+        self.check_memory_access(ptr, 0)?;
+        let size = self.memory_size_bytes();
+        let len = arbutil::clamp_access(ptr.to_u64(), len.into(), size).unwrap_or_default();
+        unsafe { Ok(STATIC_MEM.read_slice(ptr, len as usize)) }
+    }
+
+    /// Copies `len` bytes of memory at `ptr` to later compare against. See
+    /// [`MemorySnapshot::diff_against`].
+    #[cfg(feature = "diagnostics")]
+    pub fn snapshot_region(
+        &self,
+        ptr: GuestPtr,
+        len: u32,
+    ) -> Result<MemorySnapshot, MemoryBoundsError> {
+        self.check_memory_access(ptr, len)?;
+        let data = unsafe { STATIC_MEM.read_slice(ptr, len as usize) };
+        Ok(MemorySnapshot { ptr, data })
+    }
+
+    /// Reads the slice described by a `(ptr, len)` pair of little-endian u32s stored at `desc`,
+    /// bounds checking both the pair and the slice.
+    pub fn read_slice_descriptor(&self, desc: GuestPtr) -> Result<Vec<u8>, MemoryBoundsError> {
+        self.check_memory_access(desc, 8)?;
+        let (ptr, len) = unsafe { (STATIC_MEM.read_u32(desc), STATIC_MEM.read_u32(desc + 4)) };
+        let ptr = GuestPtr(ptr);
+        self.check_memory_access(ptr, len)?;
+        unsafe { Ok(STATIC_MEM.read_slice(ptr, len as usize)) }
+    }
+
+    /// Reads `count` consecutive words, bounds checking them all at once.
+    pub fn read_bytes32_array(
+        &self,
+        ptr: GuestPtr,
+        count: u32,
+    ) -> Result<Vec<Bytes32>, MemoryBoundsError> {
+        let len = count.checked_mul(32).ok_or(MemoryBoundsError)?;
+        self.check_memory_access(ptr, len)?;
+        let data = unsafe { STATIC_MEM.read_slice(ptr, len as usize) };
+        let words = data.chunks_exact(32).map(|word| word.try_into().unwrap());
+        Ok(words.collect())
+    }
+
+    /// Writes consecutive words, bounds checking them all at once.
+    pub fn write_bytes32_array(
+        &mut self,
+        ptr: GuestPtr,
+        words: &[Bytes32],
+    ) -> Result<(), MemoryBoundsError> {
+        let count = u32::try_from(words.len()).map_err(|_| MemoryBoundsError)?;
+        let len = count.checked_mul(32).ok_or(MemoryBoundsError)?;
+        self.check_no_request_in_flight();
+        self.check_memory_access(ptr, len)?;
+        let data: Vec<u8> = words.iter().flat_map(|word| word.0).collect();
+        unsafe { STATIC_MEM.write_slice(ptr, &data) };
+        Ok(())
+    }
+
+    /// Hashes a region of memory with the given algorithm, bounds checking it just once.
+    pub fn hash_memory_with(
+        &self,
+        ptr: GuestPtr,
+        len: u32,
+        algo: HashAlgo,
+    ) -> Result<Vec<u8>, MemoryBoundsError> {
+        self.check_memory_access(ptr, len)?;
+        let preimage = unsafe { STATIC_MEM.read_slice(ptr, len as usize) };
+        Ok(algo.hash(preimage).to_vec())
+    }
+
+    /// Computes the SHA-256 hash in the host, matching the EVM's precompile at address `0x02`.
+    pub fn sha256(&self, data: &[u8]) -> Bytes32 {
+        crypto::sha256(data).into()
+    }
+
+    /// Computes the SHA-256 hash of a region of memory.
+    pub fn sha256_memory(&self, ptr: GuestPtr, len: u32) -> Result<Bytes32, MemoryBoundsError> {
+        self.check_memory_access(ptr, len)?;
+        let preimage = unsafe { STATIC_MEM.read_slice(ptr, len as usize) };
+        Ok(self.sha256(&preimage))
+    }
+
+    /// Computes the RIPEMD-160 hash in the host, matching the EVM's precompile at address `0x03`.
+    pub fn ripemd160(&self, data: &[u8]) -> Bytes20 {
+        crypto::ripemd160(data).into()
+    }
+
+    /// Computes `base ^ exp % modulus` in the host, matching the EVM's precompile at address `0x05`.
+    /// The result is as long as the modulus, and is zero if the modulus is. Operands longer than
+    /// the configured cap are rejected.
+    pub fn modexp(&self, base: &[u8], exp: &[u8], modulus: &[u8]) -> Result<Vec<u8>> {
+        let cap = self.config.max_modexp_len as usize;
+        let longest = base.len().max(exp.len()).max(modulus.len());
+        if cap != 0 && longest > cap {
+            bail!("modexp operand of {longest} bytes exceeds the {cap} byte cap");
+        }
+        Ok(arbutil::math::modexp(base, exp, modulus))
+    }
+
+    /// Adds two points on the bn256 curve in the host, matching the EVM's precompile at address
+    /// `0x06`. Each point is 64 bytes, with the point at infinity encoded as zeros.
+    pub fn bn256_add(&self, a: &[u8], b: &[u8]) -> Result<Vec<u8>> {
+        Ok(bn256::add(a, b)?.to_vec())
+    }
+
+    /// Multiplies a 64-byte point on the bn256 curve by a 32-byte scalar in the host, matching
+    /// the EVM's precompile at address `0x07`.
+    pub fn bn256_mul(&self, point: &[u8], scalar: &[u8]) -> Result<Vec<u8>> {
+        Ok(bn256::mul(point, scalar)?.to_vec())
+    }
+
+    /// Performs a bn256 pairing check in the host, matching the EVM's precompile at address `0x08`.
+    /// The input must consist of 192-byte `(G1, G2)` pairs, and may be empty.
+    pub fn bn256_pairing(&self, input: &[u8]) -> Result<bool> {
+        bn256::pairing(input)
+    }
+
+    /// Runs the BLAKE2b compression function F in the host, matching the EVM's precompile at address
+    /// `0x09`. The state `h`, message `m`, and offset `t` are 64, 128, and 16 bytes respectively.
+    pub fn blake2f(&self, rounds: u32, h: &[u8], m: &[u8], t: &[u8], f: bool) -> Result<Vec<u8>> {
+        Ok(blake2f::compress(rounds, h, m, t, f)?.to_vec())
+    }
+
+    /// Parses a `0x`-prefixed hex address, verifying its EIP-55 checksum if it's mixed-case.
+    pub fn parse_checksummed_address(&self, text: &str) -> Result<Bytes20, AddressParseError> {
+        Bytes20::from_checksummed(text)
+    }
+
+    /// Computes the canonical RLP encoding of each item in turn, concatenating the results.
+    pub fn rlp_encode(&self, items: &[RlpItem]) -> Vec<u8> {
+        rlp::encode(items)
+    }
+
+    /// Decodes a concatenation of RLP items, rejecting malformed and non-canonical encodings.
+    pub fn rlp_decode(&self, data: &[u8]) -> Result<Vec<RlpItem>> {
+        rlp::decode(data)
+    }
+
     pub fn request_handler(&mut self) -> &mut UserHostRequester {
         self.evm_api.request_handler()
     }
+
+    /// Calls the contract at the given address, sending it `value` wei and at most `gas` gas,
+    /// capped at the gas available. Returns the call's status and the gas left afterward.
+    pub fn call_contract(
+        &mut self,
+        contract: Bytes20,
+        calldata: &[u8],
+        value: Bytes32,
+        gas: u64,
+    ) -> Result<(u32, u64)> {
+        let call = |api: &mut EvmApiRequestor<_, _>, left, req| {
+            api.contract_call(contract, calldata, left, req, value)
+        };
+        self.issue_call(EvmApiMethod::ContractCall, gas, call)
+    }
+
+    /// Static calls the contract at the given address, returning the call's status and the gas
+    /// left afterward. Any programs the callee runs execute in a static context.
+    pub fn static_call(
+        &mut self,
+        contract: Bytes20,
+        calldata: &[u8],
+        gas: u64,
+    ) -> Result<(u32, u64)> {
+        let call = |api: &mut EvmApiRequestor<_, _>, left, req| {
+            api.static_call(contract, calldata, left, req)
+        };
+        self.issue_call(EvmApiMethod::StaticCall, gas, call)
+    }
+
+    /// Delegate calls the contract at the given address, returning the call's status and the gas
+    /// left afterward. The host runs the callee with this program's storage address, sender, and
+    /// call value.
+    pub fn delegate_call(
+        &mut self,
+        contract: Bytes20,
+        calldata: &[u8],
+        gas: u64,
+    ) -> Result<(u32, u64)> {
+        let call = |api: &mut EvmApiRequestor<_, _>, left, req| {
+            api.delegate_call(contract, calldata, left, req)
+        };
+        self.issue_call(EvmApiMethod::DelegateCall, gas, call)
+    }
+
+    /// Deploys a contract via `CREATE`, funding it with `value` wei. Returns the new contract's
+    /// address, or zero on failure, along with the outcome of its init code.
+    pub fn create(&mut self, init_code: &[u8], value: Bytes32) -> Result<(Bytes20, u32)> {
+        let create = |api: &mut EvmApiRequestor<_, _>, code, gas| api.create1(code, value, gas);
+        self.issue_create(EvmApiMethod::Create1, init_code, create)
+    }
+
+    /// Deploys a contract via `CREATE2`, funding it with `value` wei. Returns the new contract's
+    /// address, which matches [`evm::compute_create2_address`], or zero on failure, along with
+    /// the outcome of its init code.
+    pub fn create2(
+        &mut self,
+        init_code: &[u8],
+        salt: Bytes32,
+        value: Bytes32,
+    ) -> Result<(Bytes20, u32)> {
+        let create =
+            |api: &mut EvmApiRequestor<_, _>, code, gas| api.create2(code, value, salt, gas);
+        self.issue_create(EvmApiMethod::Create2, init_code, create)
+    }
+
+    /// Gets the size of the code at the given address via a dedicated request, which avoids
+    /// loading the code itself.
+    pub fn account_code_size(&mut self, address: Bytes20) -> Result<u32> {
+        let (size, gas_cost) = self.evm_api.account_code_size(address);
+        self.buy_gas(gas_cost)?;
+        Ok(size)
+    }
+
+    /// Gets the code hash of the account at the given address. As with the EVM's `EXTCODEHASH`
+    /// opcode, accounts without code yield the empty code hash and those that don't exist zero.
+    pub fn account_code_hash(&mut self, address: Bytes20) -> Result<Bytes32> {
+        let (hash, gas_cost) = self.evm_api.account_codehash(address);
+        self.buy_gas(gas_cost)?;
+        Ok(hash)
+    }
+
+    /// Gets the hash of the given block. As with the EVM's `BLOCKHASH` opcode, blocks outside the
+    /// window of the 256 most recent yield zero, which is determined without making a request.
+    pub fn block_hash(&mut self, number: u64) -> Result<Bytes32> {
+        self.buy_gas(evm::BLOCKHASH_GAS)?;
+        if !evm::block_hash_available(self.evm_data.block_number, number) {
+            return Ok(Bytes32::default());
+        }
+        let (hash, gas_cost) = self.evm_api.block_hash(number);
+        self.buy_gas(gas_cost)?;
+        Ok(hash)
+    }
+
+    /// Performs one of the supported deployments with all the gas available, marking it as
+    /// pending for the duration.
+    fn issue_create<F>(
+        &mut self,
+        method: EvmApiMethod,
+        init_code: &[u8],
+        create: F,
+    ) -> Result<(Bytes20, u32)>
+    where
+        F: FnOnce(
+            &mut EvmApiRequestor<VecReader, UserHostRequester>,
+            Vec<u8>,
+            u64,
+        ) -> (Result<Bytes20>, u32, u64),
+    {
+        let gas_left = self.gas_left()?;
+
+        self.pending_call = Some(method);
+        let (result, ret_len, gas_cost) = create(&mut self.evm_api, init_code.to_vec(), gas_left);
+        self.pending_call = None;
+
+        let address = result?;
+        self.buy_gas(gas_cost)?;
+        self.evm_data.return_data_len = ret_len;
+
+        let status = if address == Bytes20::default() {
+            UserOutcomeKind::Revert
+        } else {
+            UserOutcomeKind::Success
+        };
+        Ok((address, status as u32))
+    }
+
+    /// Performs one of the supported EVM calls, marking it as pending for the duration.
+    fn issue_call<F>(&mut self, method: EvmApiMethod, gas: u64, call: F) -> Result<(u32, u64)>
+    where
+        F: FnOnce(
+            &mut EvmApiRequestor<VecReader, UserHostRequester>,
+            u64,
+            u64,
+        ) -> (u32, u64, UserOutcomeKind),
+    {
+        let gas_left = self.gas_left()?;
+        let gas_req = gas.min(gas_left);
+
+        self.pending_call = Some(method);
+        let (outs_len, gas_cost, status) = call(&mut self.evm_api, gas_left, gas_req);
+        self.pending_call = None;
+
+        self.buy_gas(gas_cost)?;
+        self.evm_data.return_data_len = outs_len;
+        Ok((status as u32, self.gas_left()?))
+    }
+}
+
+impl OutputSink for &mut Program {
+    fn append(&mut self, data: &[u8]) {
+        self.append_outs(data);
+    }
 }
 
 #[allow(clippy::unit_arg)]
@@ -237,6 +1497,10 @@ impl UserHost<VecReader> for Program {
         &self.evm_data
     }
 
+    fn min_gas_reserve(&self) -> u64 {
+        self.config.min_gas_reserve
+    }
+
     fn evm_return_data_len(&mut self) -> &mut u32 {
         &mut self.evm_data.return_data_len
     }
@@ -252,20 +1516,37 @@ impl UserHost<VecReader> for Program {
     }
 
     fn write_u32(&mut self, ptr: GuestPtr, x: u32) -> Result<(), MemoryBoundsError> {
+        self.check_no_request_in_flight();
         self.check_memory_access(ptr, 4)?;
         unsafe { Ok(STATIC_MEM.write_u32(ptr, x)) }
     }
 
     fn write_slice(&self, ptr: GuestPtr, src: &[u8]) -> Result<(), MemoryBoundsError> {
+        self.check_no_request_in_flight();
         self.check_memory_access(ptr, src.len() as u32)?;
         unsafe { Ok(STATIC_MEM.write_slice(ptr, src)) }
     }
 
     fn say<D: Display>(&self, text: D) {
-        println!("{} {text}", "Stylus says:".yellow());
+        if self.config.suppress_say {
+            return;
+        }
+        if let Some(text) = LineCapture::record(&SAY_CAPTURE, text) {
+            println!("{} {text}", "Stylus says:".yellow());
+        }
     }
 
     fn trace(&mut self, name: &str, args: &[u8], outs: &[u8], _end_ink: u64) {
+        let limit = self.config.max_trace_arg_bytes;
+        let args = &arbutil::truncate_trace_data(args, limit);
+        let outs = &arbutil::truncate_trace_data(outs, limit);
+
+        // the sink is taken while it runs so that it can view the program
+        if let Some(mut sink) = self.trace_sink.take() {
+            sink(self.view(), name, args, outs);
+            self.trace_sink = Some(sink);
+            return;
+        }
         let args = hex::encode(args);
         let outs = hex::encode(outs);
         println!("Error: unexpected hostio tracing info for {name} while proving: {args}, {outs}");
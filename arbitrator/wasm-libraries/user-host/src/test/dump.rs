@@ -0,0 +1,81 @@
+// Copyright 2024, Offchain Labs, Inc.
+// For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+use crate::{dump::DecodeError, program::Program, test::TestHost};
+use arbutil::{
+    evm::{
+        api::{EvmApiMethod, EVM_API_METHOD_REQ_OFFSET},
+        EvmData,
+    },
+    Bytes20, Bytes32,
+};
+use prover::programs::config::StylusConfig;
+
+#[test]
+fn test_dump_round_trip() {
+    let host = TestHost::new();
+    let evm_data = EvmData {
+        chainid: 42161,
+        block_randomness: Bytes32([3; 32]),
+        msg_sender: Bytes20([4; 20]),
+        read_only: true,
+        ..EvmData::default()
+    };
+    let config = StylusConfig {
+        min_gas_reserve: 7,
+        suppress_say: true,
+        fuzz_seed: 0xdead,
+        max_call_depth: 5,
+        ..StylusConfig::default()
+    };
+    let program = host.push(b"args", evm_data, config);
+    program.append_outs(b"outs");
+    let req_type = EvmApiMethod::GetBytes32 as u32 + EVM_API_METHOD_REQ_OFFSET;
+    unsafe { program.request_handler().set_request(req_type, &[1; 32]) };
+
+    let dump = program.dump();
+    let loaded = Program::load(&dump).unwrap();
+    assert_eq!(loaded.encode(), dump);
+    assert_eq!(loaded.args, b"args");
+    assert_eq!(loaded.outs, b"outs");
+    assert_eq!(loaded.module, program.module);
+    assert_eq!(loaded.evm_data.chainid, 42161);
+    assert_eq!(loaded.evm_data.block_randomness, Bytes32([3; 32]));
+    assert_eq!(loaded.evm_data.msg_sender, Bytes20([4; 20]));
+    assert!(loaded.evm_data.read_only);
+    assert_eq!(loaded.config.min_gas_reserve, 7);
+    assert!(loaded.config.suppress_say);
+    assert_eq!(loaded.config.fuzz_seed, 0xdead);
+    assert_eq!(loaded.config.max_call_depth, 5);
+    assert_eq!(loaded.pending_request, Some((req_type, vec![1; 32])));
+    assert_eq!(dump[0], 1);
+}
+
+#[test]
+fn test_dump_decode_errors() {
+    let host = TestHost::new();
+    let dump = host.push_default().dump();
+
+    let mut other_version = dump.clone();
+    other_version[0] = 2;
+    let unknown = Program::load(&other_version).unwrap_err();
+    assert_eq!(unknown, DecodeError::UnknownVersion(2));
+
+    let truncated = Program::load(&dump[..dump.len() - 1]).unwrap_err();
+    assert_eq!(truncated, DecodeError::Truncated);
+
+    let mut trailing = dump.clone();
+    trailing.extend([0; 3]);
+    assert_eq!(
+        Program::load(&trailing).unwrap_err(),
+        DecodeError::TrailingBytes(3)
+    );
+
+    // the last byte says whether a request is pending
+    let mut invalid = dump;
+    *invalid.last_mut().unwrap() = 2;
+    assert_eq!(
+        Program::load(&invalid).unwrap_err(),
+        DecodeError::InvalidFlag(2)
+    );
+}
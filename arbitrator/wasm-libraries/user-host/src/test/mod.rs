@@ -0,0 +1,292 @@
+// Copyright 2024, Offchain Labs, Inc.
+// For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+//! Stands in for the replay machine so that programs can be tested natively.
+//!
+//! Each import the user host makes of the machine is defined here against thread-local state,
+//! including Geth's side of the request protocol, which is driven through the same `programs__*`
+//! exports the Go host calls.
+
+use crate::{
+    link,
+    program::{self, Program},
+};
+use arbutil::evm::{api::EvmApiMethod, EvmData};
+use caller_env::{static_caller::STATIC_MEM, GuestPtr, MemAccess};
+use prover::programs::config::StylusConfig;
+use std::{
+    cell::RefCell,
+    sync::{Mutex, MutexGuard},
+};
+use wasmer_types::WASM_PAGE_SIZE;
+
+#[cfg(feature = "diagnostics")]
+mod dump;
+mod program;
+mod request;
+
+/// The size of each program's memory in pages.
+const PROGRAM_PAGES: u32 = 1;
+
+/// Where the host stages requests and their answers, just beyond the program's memory.
+const SCRATCH: GuestPtr = GuestPtr(PROGRAM_PAGES * WASM_PAGE_SIZE as u32);
+
+/// How the host answers a request, given its method and payload, as `(result, raw_data, gas)`.
+pub(crate) type Responder = Box<dyn FnMut(EvmApiMethod, &[u8]) -> (Vec<u8>, Vec<u8>, u64)>;
+
+/// The machine's side of every import.
+struct HostState {
+    /// The program's memory followed by the host's scratch space.
+    memory: Vec<u8>,
+    /// The size of the program's memory in pages, as reported to the user host.
+    memory_pages: u32,
+    ink_left: u64,
+    ink_status: u32,
+    stack_left: u32,
+    /// The number of times the program was told to trap.
+    traps: usize,
+    /// The number of modules linked so far, which is also the index of the last.
+    modules: u32,
+    /// Answers requests with zeros if unset.
+    responder: Option<Responder>,
+    /// Every request answered, in order.
+    requests: Vec<(EvmApiMethod, Vec<u8>)>,
+    /// The id of every request answered, in order.
+    request_ids: Vec<u32>,
+}
+
+impl Default for HostState {
+    fn default() -> Self {
+        Self {
+            memory: vec![0; 2 * PROGRAM_PAGES as usize * WASM_PAGE_SIZE],
+            memory_pages: PROGRAM_PAGES,
+            ink_left: 1_000_000_000,
+            ink_status: 0,
+            stack_left: u32::MAX,
+            traps: 0,
+            modules: 0,
+            responder: None,
+            requests: vec![],
+            request_ids: vec![],
+        }
+    }
+}
+
+thread_local! {
+    static HOST: RefCell<HostState> = RefCell::new(HostState::default());
+}
+
+fn with_host<T>(f: impl FnOnce(&mut HostState) -> T) -> T {
+    HOST.with_borrow_mut(f)
+}
+
+/// The stack of programs is global, so tests mustn't run concurrently.
+static HOST_LOCK: Mutex<()> = Mutex::new(());
+
+/// Exclusive access to a freshly reset host, whose programs are removed when dropped.
+pub(crate) struct TestHost {
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl TestHost {
+    pub fn new() -> Self {
+        // a test that panics poisons the lock, which is harmless as everything is reset here
+        let guard = HOST_LOCK
+            .lock()
+            .unwrap_or_else(|poison| poison.into_inner());
+        Program::clear_all();
+        #[cfg(feature = "experimental")]
+        Program::set_host_limits(program::HostLimits::NONE);
+        unsafe { program::reset_request_id_counter() };
+        with_host(|host| *host = HostState::default());
+        Self { _guard: guard }
+    }
+
+    /// Links a new module and pushes a program for it, as `programs__new_program` would.
+    pub fn push(
+        &self,
+        args: &[u8],
+        evm_data: EvmData,
+        config: StylusConfig,
+    ) -> &'static mut Program {
+        let module = with_host(|host| {
+            host.modules += 1;
+            host.modules
+        });
+        Program::push_new(args.to_vec(), evm_data, module, config);
+        Program::current()
+    }
+
+    /// Pushes a program with the default context and config.
+    pub fn push_default(&self) -> &'static mut Program {
+        self.push(&[], EvmData::default(), StylusConfig::default())
+    }
+
+    /// Sets how the host answers requests from here on.
+    pub fn respond_with(
+        &self,
+        responder: impl FnMut(EvmApiMethod, &[u8]) -> (Vec<u8>, Vec<u8>, u64) + 'static,
+    ) {
+        with_host(|host| host.responder = Some(Box::new(responder)));
+    }
+
+    /// Provides every request answered so far, in order.
+    pub fn requests(&self) -> Vec<(EvmApiMethod, Vec<u8>)> {
+        with_host(|host| host.requests.clone())
+    }
+
+    /// Provides the id of every request answered so far, in order.
+    pub fn request_ids(&self) -> Vec<u32> {
+        with_host(|host| host.request_ids.clone())
+    }
+
+    /// Provides the number of times the program was told to trap.
+    pub fn traps(&self) -> usize {
+        with_host(|host| host.traps)
+    }
+
+    pub fn ink_left(&self) -> u64 {
+        with_host(|host| host.ink_left)
+    }
+
+    pub fn set_ink(&self, ink: u64) {
+        with_host(|host| host.ink_left = ink);
+    }
+
+    /// Grows the program's memory by `pages`, as the machine does once `pay_for_memory_grow`
+    /// returns. Only the reported size changes, so the pages gained can't be accessed.
+    pub fn grow_memory(&self, pages: u32) {
+        with_host(|host| host.memory_pages += pages);
+    }
+
+    pub fn read_memory(&self, ptr: u32, len: usize) -> Vec<u8> {
+        let ptr = ptr as usize;
+        with_host(|host| host.memory[ptr..ptr + len].to_vec())
+    }
+
+    pub fn write_memory(&self, ptr: u32, data: &[u8]) {
+        let ptr = ptr as usize;
+        with_host(|host| host.memory[ptr..ptr + data.len()].copy_from_slice(data));
+    }
+}
+
+impl Drop for TestHost {
+    fn drop(&mut self) {
+        Program::clear_all();
+    }
+}
+
+/// Plays Geth's part, fetching the request through the exports the Go host calls and
+/// answering it via the responder.
+#[no_mangle]
+unsafe extern "C" fn program_request(id: u32) -> u32 {
+    let req_type = link::programs__get_request(id, SCRATCH);
+    let len = STATIC_MEM.read_u32(SCRATCH) as usize;
+    link::programs__get_request_data(id, SCRATCH);
+    let data = STATIC_MEM.read_slice(SCRATCH, len);
+    let method = EvmApiMethod::from_request_status(req_type).expect("not a request");
+
+    // the responder is taken while it runs so that it may use the host
+    let mut responder = with_host(|host| {
+        host.requests.push((method, data.clone()));
+        host.request_ids.push(id);
+        host.responder.take()
+    });
+    let (result, raw_data, gas) = match &mut responder {
+        Some(responder) => responder(method, &data),
+        None => (vec![0; 32], vec![], 0),
+    };
+    with_host(|host| host.responder = host.responder.take().or(responder));
+
+    let raw_data_ptr = SCRATCH + result.len() as u32;
+    STATIC_MEM.write_slice(SCRATCH, &result);
+    STATIC_MEM.write_slice(raw_data_ptr, &raw_data);
+    link::programs__set_response(id, gas, SCRATCH, result.len(), raw_data_ptr, raw_data.len());
+    id
+}
+
+#[no_mangle]
+unsafe extern "C" fn program_memory_size(_module: u32) -> u32 {
+    with_host(|host| host.memory_pages)
+}
+
+#[no_mangle]
+unsafe extern "C" fn set_trap() {
+    with_host(|host| host.traps += 1);
+}
+
+#[no_mangle]
+unsafe extern "C" fn user_ink_left() -> u64 {
+    with_host(|host| host.ink_left)
+}
+
+#[no_mangle]
+unsafe extern "C" fn user_ink_status() -> u32 {
+    with_host(|host| host.ink_status)
+}
+
+#[no_mangle]
+unsafe extern "C" fn user_set_ink(ink: u64, status: u32) {
+    with_host(|host| {
+        host.ink_left = ink;
+        host.ink_status = status;
+    });
+}
+
+#[no_mangle]
+unsafe extern "C" fn wavm_link_module(_hash: *const u8) -> u32 {
+    with_host(|host| {
+        host.modules += 1;
+        host.modules
+    })
+}
+
+#[no_mangle]
+unsafe extern "C" fn wavm_unlink_module() {}
+
+#[no_mangle]
+unsafe extern "C" fn program_set_ink(_module: u32, ink: u64) {
+    with_host(|host| host.ink_left = ink);
+}
+
+#[no_mangle]
+unsafe extern "C" fn program_set_stack(_module: u32, stack: u32) {
+    with_host(|host| host.stack_left = stack);
+}
+
+#[no_mangle]
+unsafe extern "C" fn program_ink_left(_module: u32) -> u64 {
+    with_host(|host| host.ink_left)
+}
+
+#[no_mangle]
+unsafe extern "C" fn program_ink_status(_module: u32) -> u32 {
+    with_host(|host| host.ink_status)
+}
+
+#[no_mangle]
+unsafe extern "C" fn program_stack_left(_module: u32) -> u32 {
+    with_host(|host| host.stack_left)
+}
+
+#[no_mangle]
+unsafe extern "C" fn wavm_caller_load8(ptr: GuestPtr) -> u8 {
+    with_host(|host| host.memory[ptr.0 as usize])
+}
+
+#[no_mangle]
+unsafe extern "C" fn wavm_caller_load32(ptr: GuestPtr) -> u32 {
+    let ptr = ptr.0 as usize;
+    with_host(|host| u32::from_le_bytes(host.memory[ptr..ptr + 4].try_into().unwrap()))
+}
+
+#[no_mangle]
+unsafe extern "C" fn wavm_caller_store8(ptr: GuestPtr, x: u8) {
+    with_host(|host| host.memory[ptr.0 as usize] = x);
+}
+
+#[no_mangle]
+unsafe extern "C" fn wavm_caller_store32(ptr: GuestPtr, x: u32) {
+    let ptr = ptr.0 as usize;
+    with_host(|host| host.memory[ptr..ptr + 4].copy_from_slice(&x.to_le_bytes()));
+}
@@ -0,0 +1,1238 @@
+// Copyright 2024, Offchain Labs, Inc.
+// For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+#[cfg(feature = "experimental")]
+use crate::program::HostLimits;
+use crate::{host, link, program::Program, test::TestHost};
+use arbutil::{
+    evm::{
+        abi::OutputWriter,
+        api::{EvmApi, EvmApiMethod, EVM_API_METHOD_REQ_OFFSET},
+        user::UserOutcomeKind,
+        EvmData, EMPTY_CODE_HASH,
+    },
+    pricing::{EVM_API_INK, HOSTIO_INK},
+    Bytes20, Bytes32,
+};
+use caller_env::GuestPtr;
+use prover::programs::{config::PricingParams, meter::read_ink, prelude::*};
+use user_host_trait::UserHost;
+use wasmer_types::WASM_PAGE_SIZE;
+
+#[test]
+fn test_push_pop() {
+    let host = TestHost::new();
+    let outer = host.push(&[1], EvmData::default(), StylusConfig::default());
+    let outer_module = outer.module;
+    let inner = host.push(&[2], EvmData::default(), StylusConfig::default());
+    assert_ne!(inner.module, outer_module);
+    assert_eq!(Program::depth(), 2);
+    assert_eq!(Program::current().args, [2]);
+
+    Program::pop();
+    assert_eq!(Program::depth(), 1);
+    assert_eq!(Program::current().module, outer_module);
+    Program::pop();
+    assert_eq!(Program::depth(), 0);
+}
+
+#[test]
+fn test_request_round_trip() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    host.respond_with(|method, _| {
+        assert_eq!(method, EvmApiMethod::GetBytes32);
+        (vec![0xaa; 32], vec![], 5)
+    });
+
+    let key = Bytes32([1; 32]);
+    let (value, _) = program.evm_api.get_bytes32(key);
+    assert_eq!(value, Bytes32([0xaa; 32]));
+    assert_eq!(host.requests(), [(EvmApiMethod::GetBytes32, key.to_vec())]);
+    assert_eq!(program.request_handler().gas_used(), 5);
+    assert_eq!(program.request_handler().pending_request(), None);
+    assert_eq!(host.traps(), 0);
+}
+
+#[test]
+fn test_output() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    program.append_outs(b"hello ");
+    program.append_outs(b"world");
+    assert_eq!(program.output(), b"hello world");
+    assert_eq!(program.output_len(), 11);
+    assert_eq!(program.outs_high_water(), 11);
+
+    program.outs.clear();
+    program.append_outs(b"bye");
+    assert_eq!(program.output(), b"bye");
+    assert_eq!(program.outs_high_water(), 11);
+}
+
+#[test]
+fn test_static_context() {
+    let host = TestHost::new();
+    let outer = host.push_default();
+    assert!(!outer.is_static());
+
+    // the host decides, regardless of how the caller made the call
+    let evm_data = EvmData {
+        read_only: true,
+        ..EvmData::default()
+    };
+    let inner = host.push(&[], evm_data, StylusConfig::default());
+    assert!(inner.is_static());
+    assert!(inner.fork_state().evm_data.read_only);
+
+    let module = inner.module;
+    inner.reset(vec![], EvmData::default(), module, StylusConfig::default());
+    assert!(!inner.is_static());
+}
+
+#[test]
+fn test_gas_left() {
+    let host = TestHost::new();
+    let config = StylusConfig {
+        pricing: PricingParams::new(10),
+        ..StylusConfig::default()
+    };
+    let program = host.push(&[], EvmData::default(), config);
+
+    // the meter says what's left
+    host.set_ink(5_000);
+    assert_eq!(program.gas_left().unwrap(), 500);
+    program.buy_gas(100).unwrap();
+    assert_eq!(program.gas_left().unwrap(), 400);
+    assert_eq!(host.ink_left(), 4_000);
+
+    assert!(program.buy_gas(1_000).is_err());
+    assert!(program.gas_left().is_err());
+}
+
+#[test]
+fn test_depth_remaining() {
+    let host = TestHost::new();
+    let config = StylusConfig {
+        max_call_depth: 5,
+        ..StylusConfig::default()
+    };
+    host.push(&[], EvmData::default(), config);
+    let program = host.push(&[], EvmData::default(), config);
+    assert_eq!(Program::depth(), 2);
+    assert_eq!(program.depth_remaining(), 3);
+
+    for _ in 0..3 {
+        host.push(&[], EvmData::default(), config);
+    }
+    assert_eq!(Program::current().depth_remaining(), 0);
+    host.push(&[], EvmData::default(), config);
+    assert_eq!(Program::current().depth_remaining(), 0);
+}
+
+#[test]
+fn test_write_output_abi() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    program.write_output_abi(|writer| {
+        writer.write_address(Bytes20([0x11; 20])).write_bytes(b"hi");
+    });
+    assert_eq!(program.output_len(), 4 * 32);
+    assert_eq!(program.outs_high_water(), 4 * 32);
+    assert!(!program.output_truncated());
+}
+
+#[test]
+#[cfg(feature = "experimental")]
+fn test_write_output_abi_limit() {
+    let host = TestHost::new();
+    Program::set_host_limits(HostLimits {
+        max_output_bytes: 40,
+        ..HostLimits::default()
+    });
+
+    // the output limit applies as it would to any other append
+    let program = host.push_default();
+    program.write_output_abi(|writer| {
+        writer
+            .write_u256(Bytes32([1; 32]))
+            .write_u256(Bytes32([2; 32]));
+    });
+    assert_eq!(program.output_len(), 40);
+    assert!(program.output_truncated());
+}
+
+#[test]
+fn test_flush_output() {
+    let host = TestHost::new();
+    let args = b"hello world";
+    let program = host.push(args, EvmData::default(), StylusConfig::default());
+
+    // as in the stylus crate's flush.wat
+    unsafe {
+        host::user_host__read_args(GuestPtr(0));
+        host::user_host__write_result(GuestPtr(0), 5);
+        host::user_host__flush_output();
+        host::user_host__write_result(GuestPtr(5), 6);
+    }
+    assert_eq!(host.traps(), 0);
+    assert_eq!(
+        host.requests(),
+        [(EvmApiMethod::FlushOutput, b"hello".to_vec())]
+    );
+    assert_eq!(program.output(), b" world");
+
+    // the host prepends what was flushed, just as it does natively
+    let flushed = &host.requests()[0].1;
+    assert_eq!([flushed.as_slice(), program.output()].concat(), args);
+}
+
+#[test]
+#[cfg(feature = "diagnostics")]
+fn test_active_hostio() {
+    use std::{cell::Cell, rc::Rc};
+
+    let host = TestHost::new();
+    host.push_default();
+
+    // the hostio is known while the host answers its request
+    let active = Rc::new(Cell::new(None));
+    let seen = active.clone();
+    host.respond_with(move |_, _| {
+        seen.set(host::active_hostio());
+        (vec![0; 32], vec![], 0)
+    });
+    unsafe { host::user_host__storage_load_bytes32(GuestPtr(0), GuestPtr(32)) };
+    assert_eq!(active.get(), Some("storage_load_bytes32"));
+    assert_eq!(host::active_hostio(), None);
+}
+
+#[test]
+#[cfg(feature = "experimental")]
+fn test_hostio_surcharge() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    let cost_of = |hostio: unsafe extern "C" fn(GuestPtr)| {
+        host.set_ink(1_000_000);
+        unsafe { hostio(GuestPtr(0)) };
+        1_000_000 - host.ink_left()
+    };
+    let msg_value = cost_of(host::user_host__msg_value);
+    let msg_sender = cost_of(host::user_host__msg_sender);
+
+    program.set_hostio_surcharge("msg_value", 500);
+    assert_eq!(cost_of(host::user_host__msg_value), msg_value + 500);
+    assert_eq!(cost_of(host::user_host__msg_sender), msg_sender);
+
+    program.set_hostio_surcharge("msg_value", 0);
+    assert_eq!(cost_of(host::user_host__msg_value), msg_value);
+    assert_eq!(host.traps(), 0);
+
+    // a surcharge that can't be paid traps before the hostio runs
+    program.set_hostio_surcharge("msg_value", 2_000_000);
+    cost_of(host::user_host__msg_value);
+    assert_eq!(host.traps(), 1);
+}
+
+#[test]
+#[cfg(feature = "experimental")]
+fn test_allowed_hostios() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    program.set_allowed_hostios(Some(["msg_value"].into_iter().collect()));
+
+    // a denied hostio traps without running
+    host.write_memory(0, &[0xff; 32]);
+    unsafe { host::user_host__msg_sender(GuestPtr(0)) };
+    assert_eq!(host.traps(), 1);
+    assert_eq!(host.read_memory(0, 32), [0xff; 32]);
+
+    unsafe { host::user_host__msg_value(GuestPtr(0)) };
+    assert_eq!(host.traps(), 1);
+    assert_eq!(host.read_memory(0, 32), [0; 32]);
+
+    program.set_allowed_hostios(None);
+    unsafe { host::user_host__msg_sender(GuestPtr(0)) };
+    assert_eq!(host.traps(), 1);
+}
+
+#[test]
+fn test_bounds_errors() {
+    let host = TestHost::new();
+    let edge = GuestPtr(WASM_PAGE_SIZE as u32 - 2);
+    let past = GuestPtr(WASM_PAGE_SIZE as u32 + 1);
+
+    // out-of-bounds accesses are errors the caller may recover from
+    let program = host.push_default();
+    assert_eq!(program.read_slice_clamped(edge, 4).unwrap(), [0; 2]);
+    program.read_slice_clamped(past, 4).unwrap_err();
+    assert_eq!(host.traps(), 0);
+}
+
+#[test]
+#[cfg(feature = "experimental")]
+fn test_trap_on_bounds_error() {
+    let host = TestHost::new();
+    let edge = GuestPtr(WASM_PAGE_SIZE as u32 - 2);
+    let past = GuestPtr(WASM_PAGE_SIZE as u32 + 1);
+
+    // the host may trap regardless of whether the caller recovers
+    Program::set_host_limits(HostLimits {
+        trap_on_bounds_error: true,
+        ..HostLimits::default()
+    });
+    let program = host.push_default();
+    assert_eq!(program.read_slice_clamped(edge, 4).unwrap(), [0; 2]);
+    assert_eq!(host.traps(), 0);
+    program.read_slice_clamped(past, 4).unwrap_err();
+    assert_eq!(host.traps(), 1);
+}
+
+#[test]
+fn test_bytes32_arrays() {
+    let host = TestHost::new();
+    let program = host.push_default();
+
+    // an empty array touches no memory, even at the very end
+    let end = GuestPtr(WASM_PAGE_SIZE as u32);
+    assert!(program.read_bytes32_array(end, 0).unwrap().is_empty());
+    program.write_bytes32_array(end, &[]).unwrap();
+
+    let words: Vec<_> = (1..=4).map(|i| Bytes32([i; 32])).collect();
+    program.write_bytes32_array(GuestPtr(32), &words).unwrap();
+    assert_eq!(host.read_memory(32, 4 * 32), words.concat());
+    assert_eq!(program.read_bytes32_array(GuestPtr(32), 4).unwrap(), words);
+
+    // the whole array is checked before any of it is read or written
+    let last = GuestPtr(WASM_PAGE_SIZE as u32 - 3 * 32);
+    program.read_bytes32_array(last, 4).unwrap_err();
+    program.write_bytes32_array(last, &words).unwrap_err();
+    assert_eq!(host.read_memory(last.0, 3 * 32), [0; 3 * 32]);
+    program
+        .read_bytes32_array(GuestPtr(0), u32::MAX)
+        .unwrap_err();
+    assert_eq!(host.traps(), 0);
+}
+
+/// Runs `f` with plenty of ink, providing its result and the ink it used.
+fn ink_used<T>(host: &TestHost, f: impl FnOnce() -> T) -> (T, u64) {
+    host.set_ink(1_000_000_000);
+    let result = f();
+    (result, 1_000_000_000 - host.ink_left())
+}
+
+#[test]
+fn test_word_access() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    host.write_memory(0, &[0xff; 64]);
+
+    // integers are little-endian and cost nothing beyond the hostio using them
+    let x = u128::from_le_bytes(std::array::from_fn(|i| i as u8));
+    let (result, ink) = ink_used(&host, || program.write_u128(GuestPtr(8), x));
+    result.unwrap();
+    assert_eq!(ink, 0);
+    assert_eq!(host.read_memory(8, 16), x.to_le_bytes());
+    assert_eq!(host.read_memory(0, 8), [0xff; 8]);
+    assert_eq!(host.read_memory(24, 8), [0xff; 8]);
+    assert_eq!(
+        ink_used(&host, || program.read_u128(GuestPtr(8)).unwrap()),
+        (x, 0)
+    );
+
+    // EVM words keep their byte order
+    let word = Bytes32(std::array::from_fn(|i| i as u8));
+    let (result, ink) = ink_used(&host, || program.write_u256_be(GuestPtr(64), word));
+    result.unwrap();
+    assert_eq!(ink, 0);
+    assert_eq!(host.read_memory(64, 32), word.0);
+    assert_eq!(
+        ink_used(&host, || program.read_u256_be(GuestPtr(64)).unwrap()),
+        (word, 0)
+    );
+
+    let last = GuestPtr(WASM_PAGE_SIZE as u32 - 8);
+    program.read_u128(last).unwrap_err();
+    program.write_u128(last, x).unwrap_err();
+    program.read_u256_be(last).unwrap_err();
+    program.write_u256_be(last, word).unwrap_err();
+    assert_eq!(host.read_memory(last.0, 8), [0; 8]);
+}
+
+#[test]
+fn test_read_string() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    let text = "héllo";
+    host.write_memory(16, text.as_bytes());
+
+    let len = text.len() as u32;
+    let (result, ink) = ink_used(&host, || program.read_string(GuestPtr(16), len));
+    assert_eq!(result.unwrap(), text);
+    assert_eq!(ink, 0);
+
+    // cutting the é in half leaves invalid UTF-8
+    let error = program.read_string(GuestPtr(16), 2).unwrap_err();
+    assert_eq!(error.to_string(), "invalid UTF-8 at byte 1 of string at 16");
+
+    let last = GuestPtr(WASM_PAGE_SIZE as u32 - 2);
+    program.read_string(last, len).unwrap_err();
+}
+
+#[test]
+fn test_write_slice_metered() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    let data = [0xaa; 64];
+
+    // the first 32 bytes are covered by the base cost
+    let (result, ink) = ink_used(&host, || program.write_slice_metered(GuestPtr(8), &data));
+    result.unwrap();
+    assert_eq!(ink, 5040 + 30 * 32);
+    assert_eq!(host.read_memory(8, 64), data);
+
+    let (result, ink) = ink_used(&host, || program.write_slice_metered(GuestPtr(8), &[]));
+    result.unwrap();
+    assert_eq!(ink, 5040);
+
+    // a write that can't be afforded leaves memory untouched
+    host.write_memory(8, &[0; 64]);
+    host.set_ink(5040);
+    program.write_slice_metered(GuestPtr(8), &data).unwrap_err();
+    assert_eq!(host.read_memory(8, 64), [0; 64]);
+}
+
+#[test]
+fn test_prefetch_storage() {
+    let host = TestHost::new();
+    let config = StylusConfig {
+        pricing: PricingParams::new(10),
+        ..StylusConfig::default()
+    };
+    let program = host.push(&[], EvmData::default(), config);
+    host.respond_with(|_, keys| (vec![0; keys.len()], vec![], 2100 * (keys.len() as u64 / 32)));
+
+    // the duplicate key is fetched once, and the gas is bought at the ink price
+    let keys = [Bytes32([1; 32]), Bytes32([2; 32]), Bytes32([1; 32])];
+    let (result, ink) = ink_used(&host, || program.prefetch_storage(&keys));
+    result.unwrap();
+    assert_eq!(ink, HOSTIO_INK + 10 * (2 * 2100 + EVM_API_INK));
+    let expected = [keys[0].0, keys[1].0].concat();
+    assert_eq!(host.requests(), [(EvmApiMethod::PrefetchBytes32, expected)]);
+
+    // cached keys aren't fetched again
+    let (result, ink) = ink_used(&host, || program.prefetch_storage(&keys[..2]));
+    result.unwrap();
+    assert_eq!(ink, HOSTIO_INK);
+    assert_eq!(host.requests().len(), 1);
+    assert_eq!(host.traps(), 0);
+}
+
+#[test]
+fn test_block_randomness() {
+    let host = TestHost::new();
+    let randomness = Bytes32([0x5a; 32]);
+
+    // as Go passes it, with every other field zero
+    host.write_memory(64, &randomness.0);
+    let zero = GuestPtr(0);
+    let evm_data = unsafe {
+        let evm_data = link::programs__create_evm_data(
+            zero,
+            0,
+            zero,
+            0,
+            0,
+            0,
+            GuestPtr(64),
+            zero,
+            zero,
+            zero,
+            zero,
+            zero,
+            zero,
+            0,
+            0,
+            0,
+        );
+        *Box::from_raw(evm_data as *mut EvmData)
+    };
+    let program = host.push(&[], evm_data, StylusConfig::default());
+    assert_eq!(program.block_randomness(), randomness);
+    assert_eq!(program.base_fee(), Bytes32::default());
+}
+
+#[test]
+fn test_chain_id() {
+    let host = TestHost::new();
+    let evm_data = EvmData {
+        chainid: 42161,
+        ..EvmData::default()
+    };
+    let program = host.push(&[], evm_data, StylusConfig::default());
+    assert_eq!(program.chain_id(), 42161);
+    assert_eq!(unsafe { host::user_host__chainid() }, 42161);
+    assert!(host.requests().is_empty());
+}
+
+#[test]
+fn test_base_fee() {
+    let host = TestHost::new();
+    let basefee = Bytes32::from(100_000_000_u64);
+    let evm_data = EvmData {
+        block_basefee: basefee,
+        ..EvmData::default()
+    };
+    let program = host.push(&[], evm_data, StylusConfig::default());
+    assert_eq!(program.base_fee(), basefee);
+
+    unsafe { host::user_host__block_basefee(GuestPtr(0)) };
+    assert_eq!(host.read_memory(0, 32), basefee.0);
+    assert!(host.requests().is_empty());
+}
+
+#[test]
+fn test_outs_high_water() {
+    let host = TestHost::new();
+    let program = host.push_default();
+
+    // the write_result hostio replaces the output rather than appending to it
+    unsafe { host::user_host__write_result(GuestPtr(0), 64) };
+    assert_eq!(program.outs_high_water(), 64);
+    unsafe { host::user_host__write_result(GuestPtr(0), 16) };
+    assert_eq!(program.output_len(), 16);
+    assert_eq!(program.outs_high_water(), 64);
+
+    program.append_outs(&[1; 100]);
+    assert_eq!(program.outs_high_water(), 116);
+    assert_eq!(host.traps(), 0);
+
+    let module = program.module;
+    program.reset(vec![], EvmData::default(), module, StylusConfig::default());
+    assert_eq!(program.outs_high_water(), 0);
+}
+
+#[test]
+fn test_clear_all() {
+    let host = TestHost::new();
+    let req_type = EvmApiMethod::GetBytes32 as u32 + EVM_API_METHOD_REQ_OFFSET;
+    for _ in 0..3 {
+        let program = host.push_default();
+        unsafe { program.request_handler().set_request(req_type, &[0; 32]) };
+    }
+    assert_eq!(Program::depth(), 3);
+
+    let capture = Program::capture_warnings();
+    Program::clear_all();
+    assert_eq!(Program::depth(), 0);
+    assert_eq!(capture.into_lines().len(), 3);
+
+    // the abandoned requests no longer count against the budget
+    #[cfg(feature = "experimental")]
+    {
+        Program::set_host_limits(HostLimits {
+            max_total_request_bytes: 32,
+            ..HostLimits::default()
+        });
+        let program = host.push_default();
+        program.evm_api.get_bytes32(Bytes32([1; 32]));
+        assert_eq!(program.request_handler().error(), None);
+        assert_eq!(host.requests().len(), 1);
+    }
+}
+
+#[test]
+fn test_push_new_indexed() {
+    let _host = TestHost::new();
+    let config = StylusConfig::default();
+    let outer = Program::push_new_indexed(vec![1], EvmData::default(), 7, config);
+    let inner = Program::push_new_indexed(vec![2], EvmData::default(), 8, config);
+    assert_eq!((outer, inner), (0, 1));
+
+    assert_eq!(Program::get(outer).unwrap().module, 7);
+    assert_eq!(Program::get(inner).unwrap().args, [2]);
+    assert!(Program::get(2).is_none());
+
+    Program::pop();
+    assert!(Program::get(inner).is_none());
+    assert_eq!(Program::get(outer).unwrap().module, 7);
+}
+
+#[test]
+fn test_capture_say() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    host.write_memory(0, b"from memory");
+
+    let capture = Program::capture_say();
+    program.say("first");
+    program.console_log_text(GuestPtr(0), 11).unwrap();
+    program.say(2);
+    assert_eq!(capture.into_lines(), ["first", "from memory", "2"]);
+
+    // nothing is collected once the capture is gone
+    program.say("printed");
+    assert!(Program::capture_say().into_lines().is_empty());
+}
+
+#[test]
+fn test_stack_report() {
+    let host = TestHost::new();
+    let outer = host.push(&[1, 2, 3], EvmData::default(), StylusConfig::default());
+    outer.append_outs(&[0; 10]);
+    outer.pending_call = Some(EvmApiMethod::ContractCall);
+    let inner = host.push(&[4], EvmData::default(), StylusConfig::default());
+    let (outer, inner) = (outer.module, inner.module);
+
+    let report = Program::stack_report();
+    let frames: Vec<_> = report
+        .iter()
+        .map(|frame| {
+            let info = (frame.module, frame.depth, frame.args_len, frame.outs_len);
+            (info, frame.memory_pages, frame.pending_request)
+        })
+        .collect();
+    let pending = Some(EvmApiMethod::ContractCall);
+    assert_eq!(frames[0], ((outer, 0, 3, 10), 1, pending));
+    assert_eq!(frames[1], ((inner, 1, 1, 0), 1, None));
+    assert_eq!(frames.len(), 2);
+}
+
+#[test]
+#[cfg(feature = "experimental")]
+fn test_output_truncated() {
+    let host = TestHost::new();
+    Program::set_host_limits(HostLimits {
+        max_output_bytes: 8,
+        ..HostLimits::default()
+    });
+    let program = host.push_default();
+    program.append_outs(&[1; 4]);
+    program.append_outs(&[2; 4]);
+    assert_eq!(program.output_len(), 8);
+    assert!(!program.output_truncated());
+
+    program.append_outs(&[3]);
+    assert_eq!(program.output(), [[1; 4], [2; 4]].concat());
+    assert!(program.output_truncated());
+
+    // the flag sticks even once the output shrinks
+    program.outs.clear();
+    program.append_outs(&[4]);
+    assert!(program.output_truncated());
+
+    let module = program.module;
+    program.reset(vec![], EvmData::default(), module, StylusConfig::default());
+    assert!(!program.output_truncated());
+}
+
+#[test]
+fn test_grow_hook() {
+    use std::{cell::RefCell, rc::Rc};
+
+    let host = TestHost::new();
+    let program = host.push_default();
+    let grown = Rc::new(RefCell::new(vec![]));
+    let seen = grown.clone();
+    program.set_grow_hook(Box::new(move |old, new| seen.borrow_mut().push((old, new))));
+
+    for pages in [2, 0, 3] {
+        unsafe { host::user_host__pay_for_memory_grow(pages) };
+        host.grow_memory(pages.into());
+    }
+    assert_eq!(*grown.borrow(), [(1, 3), (3, 6)]);
+    assert_eq!(host.traps(), 0);
+}
+
+#[test]
+fn test_call_contract() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    host.respond_with(|_, _| (vec![UserOutcomeKind::Success as u8], vec![], 600));
+    let contract = Bytes20([0xcc; 20]);
+    let value = Bytes32::from(5_u64);
+
+    // the gas requested is the 8 bytes after the gas available
+    let gas_req = |request: &[u8]| u64::from_be_bytes(request[60..68].try_into().unwrap());
+
+    host.set_ink(10_000);
+    let (status, gas_left) = program
+        .call_contract(contract, b"data", value, 1_000)
+        .unwrap();
+    assert_eq!(status, UserOutcomeKind::Success as u32);
+    assert_eq!(gas_left, 9_400);
+
+    host.set_ink(10_000);
+    let (_, gas_left) = program
+        .call_contract(contract, b"data", value, 50_000)
+        .unwrap();
+    assert_eq!(gas_left, 9_400);
+
+    let requests = host.requests();
+    assert_eq!(requests[0].0, EvmApiMethod::ContractCall);
+    assert_eq!(gas_req(&requests[0].1), 1_000);
+    assert_eq!(gas_req(&requests[1].1), 10_000);
+    assert_eq!(&requests[0].1[..20], contract.0);
+    assert_eq!(&requests[0].1[20..52], value.0);
+    assert_eq!(&requests[0].1[68..], b"data");
+}
+
+#[test]
+fn test_call_value() {
+    let host = TestHost::new();
+    let value = Bytes32::from(1_000_u64);
+    let evm_data = EvmData {
+        msg_value: value,
+        ..EvmData::default()
+    };
+    let outer = host.push(&[], evm_data, StylusConfig::default());
+    assert_eq!(outer.call_value(), value);
+
+    // the host runs a delegate call with the caller's context
+    let inner = host.push(&[], evm_data, StylusConfig::default());
+    assert_eq!(inner.call_value(), value);
+    unsafe { host::user_host__msg_value(GuestPtr(0)) };
+    assert_eq!(host.read_memory(0, 32), value.0);
+    assert!(host.requests().is_empty());
+}
+
+#[test]
+fn test_sender() {
+    let host = TestHost::new();
+    let (eoa, proxy, implementation) = (Bytes20([1; 20]), Bytes20([2; 20]), Bytes20([3; 20]));
+
+    // a normal call from the account to the proxy
+    let evm_data = EvmData {
+        msg_sender: eoa,
+        contract_address: proxy,
+        ..EvmData::default()
+    };
+    let outer = host.push(&[], evm_data, StylusConfig::default());
+    assert_eq!(outer.sender(), eoa);
+
+    // a normal call from the proxy sees the proxy as its sender
+    let called = EvmData {
+        msg_sender: proxy,
+        contract_address: implementation,
+        ..evm_data
+    };
+    let inner = host.push(&[], called, StylusConfig::default());
+    assert_eq!(inner.sender(), proxy);
+    Program::pop();
+
+    // whereas a delegate call from it preserves the original sender
+    let inner = host.push(&[], evm_data, StylusConfig::default());
+    assert_eq!(inner.sender(), eoa);
+    unsafe { host::user_host__msg_sender(GuestPtr(0)) };
+    assert_eq!(host.read_memory(0, 20), eoa.0);
+}
+
+#[test]
+fn test_address() {
+    let host = TestHost::new();
+    let (proxy, implementation) = (Bytes20([2; 20]), Bytes20([3; 20]));
+
+    let direct = EvmData {
+        contract_address: implementation,
+        ..EvmData::default()
+    };
+    let program = host.push(&[], direct, StylusConfig::default());
+    assert_eq!(program.address(), implementation);
+    Program::pop();
+
+    // the implementation's code runs in the proxy's context
+    let delegated = EvmData {
+        contract_address: proxy,
+        ..EvmData::default()
+    };
+    let program = host.push(&[], delegated, StylusConfig::default());
+    assert_eq!(program.address(), proxy);
+    unsafe { host::user_host__contract_address(GuestPtr(0)) };
+    assert_eq!(host.read_memory(0, 20), proxy.0);
+}
+
+#[test]
+fn test_extensions() {
+    #[derive(Debug, PartialEq)]
+    struct Span {
+        id: u64,
+    }
+
+    let host = TestHost::new();
+    let program = host.push_default();
+    assert_eq!(program.get_extension::<Span>(), None);
+
+    program.set_extension(Span { id: 1 });
+    program.set_extension("request 7");
+    assert_eq!(program.get_extension(), Some(&Span { id: 1 }));
+    assert_eq!(program.get_extension(), Some(&"request 7"));
+
+    program.set_extension(Span { id: 2 });
+    assert_eq!(program.get_extension(), Some(&Span { id: 2 }));
+
+    let module = program.module;
+    program.reset(vec![], EvmData::default(), module, StylusConfig::default());
+    assert_eq!(program.get_extension(), Some(&Span { id: 2 }));
+}
+
+#[test]
+fn test_reset() {
+    let host = TestHost::new();
+    let program = host.push(&[1], EvmData::default(), StylusConfig::default());
+    program.append_outs(&[0; 1024]);
+    program.early_exit = Some(UserOutcomeKind::Revert);
+    program.result = Some((0, 0));
+    let capacity = program.outs.capacity();
+    let address: *const Program = program;
+
+    let evm_data = EvmData {
+        chainid: 7,
+        ..EvmData::default()
+    };
+    let module = program.module + 1;
+    program.reset(vec![2, 3], evm_data, module, StylusConfig::default());
+    assert!(std::ptr::eq(Program::current(), address));
+    assert_eq!(program.outs.capacity(), capacity);
+    assert_eq!(program.output_len(), 0);
+    assert_eq!(program.args, [2, 3]);
+    assert_eq!((program.module, program.chain_id()), (module, 7));
+    assert_eq!((program.early_exit, program.result), (None, None));
+}
+
+#[test]
+fn test_read_slice_descriptor() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    let descriptor = |ptr: u32, len: u32| [ptr.to_le_bytes(), len.to_le_bytes()].concat();
+    host.write_memory(64, b"described");
+
+    host.write_memory(0, &descriptor(64, 9));
+    assert_eq!(
+        program.read_slice_descriptor(GuestPtr(0)).unwrap(),
+        b"described"
+    );
+    host.write_memory(0, &descriptor(64, 0));
+    assert!(program
+        .read_slice_descriptor(GuestPtr(0))
+        .unwrap()
+        .is_empty());
+
+    // the described slice runs off the end of memory
+    let end = WASM_PAGE_SIZE as u32;
+    host.write_memory(0, &descriptor(end - 4, 8));
+    program.read_slice_descriptor(GuestPtr(0)).unwrap_err();
+
+    // as does the descriptor itself
+    program
+        .read_slice_descriptor(GuestPtr(end - 4))
+        .unwrap_err();
+    assert_eq!(host.traps(), 0);
+}
+
+#[test]
+fn test_suppress_say() {
+    let host = TestHost::new();
+    let config = StylusConfig {
+        suppress_say: true,
+        ..StylusConfig::default()
+    };
+    let program = host.push(&[], EvmData::default(), config);
+    host.write_memory(0, b"muted");
+
+    let capture = Program::capture_say();
+    program.say("muted");
+    program.console_log_text(GuestPtr(0), 5).unwrap();
+    assert!(capture.into_lines().is_empty());
+    assert_eq!(host.traps(), 0);
+
+    // a program that doesn't suppress it still speaks
+    let program = host.push_default();
+    let capture = Program::capture_say();
+    program.say("heard");
+    assert_eq!(capture.into_lines(), ["heard"]);
+}
+
+#[test]
+fn test_output_accessors() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    assert!(program.output().is_empty());
+    assert_eq!(program.output_len(), 0);
+
+    program.outs().extend_from_slice(b"via outs");
+    assert_eq!(program.output(), b"via outs");
+    assert_eq!(program.output_len(), 8);
+
+    program.append_outs(b", then appended");
+    assert_eq!(program.output(), b"via outs, then appended");
+    assert_eq!(program.output_len(), 23);
+}
+
+#[test]
+#[cfg(feature = "fuzzing")]
+fn test_rng() {
+    use rand::RngCore;
+
+    let host = TestHost::new();
+    let seeded = |seed| StylusConfig {
+        fuzz_seed: seed,
+        ..StylusConfig::default()
+    };
+    let draw = |program: &mut Program| [(); 8].map(|_| program.rng().next_u64());
+
+    let first = draw(host.push(&[], EvmData::default(), seeded(7)));
+    let second = draw(host.push(&[], EvmData::default(), seeded(7)));
+    let other = draw(host.push(&[], EvmData::default(), seeded(8)));
+    assert_eq!(first, second);
+    assert_ne!(first, other);
+
+    let program = Program::current();
+    let module = program.module;
+    program.reset(vec![], EvmData::default(), module, seeded(7));
+    assert_eq!(draw(program), first);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_write_while_in_flight() {
+    use std::{cell::Cell, panic, rc::Rc};
+
+    let host = TestHost::new();
+    let program = host.push_default();
+
+    // the responder can't panic itself, so the write's assertion is caught and recorded
+    let asserted = Rc::new(Cell::new(false));
+    let seen = asserted.clone();
+    host.respond_with(move |_, _| {
+        let write = || Program::current().write_slice(GuestPtr(0), &[1]);
+        seen.set(panic::catch_unwind(write).is_err());
+        (vec![0; 32], vec![], 0)
+    });
+    program.evm_api.get_bytes32(Bytes32([1; 32]));
+    assert!(asserted.get());
+    assert_eq!(host.read_memory(0, 1), [0]);
+
+    program.write_slice(GuestPtr(0), &[1]).unwrap();
+    assert_eq!(host.read_memory(0, 1), [1]);
+}
+
+#[test]
+#[cfg(feature = "experimental")]
+#[should_panic(expected = "more than 3 programs active at once")]
+fn test_absolute_max_programs() {
+    let host = TestHost::new();
+    Program::set_host_limits(HostLimits {
+        absolute_max_programs: 3,
+        ..HostLimits::default()
+    });
+    for _ in 0..3 {
+        host.push_default();
+    }
+    assert_eq!(Program::depth(), 3);
+    host.push_default();
+}
+
+#[test]
+fn test_ink_price() {
+    let host = TestHost::new();
+    let config = StylusConfig {
+        pricing: PricingParams::new(10_000),
+        ..StylusConfig::default()
+    };
+    let program = host.push(&[], EvmData::default(), config);
+    assert_eq!(program.ink_price(), 10_000);
+    assert_eq!(unsafe { host::user_host__tx_ink_price() }, 10_000);
+
+    host.set_ink(1_000_000);
+    program.buy_gas(10).unwrap();
+    assert_eq!(host.ink_left(), 1_000_000 - 10 * program.ink_price());
+}
+
+#[test]
+fn test_revert() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    program.append_outs(b"old");
+
+    let data = b"reverted data";
+    let (result, ink) = ink_used(&host, || program.revert(data));
+    result.unwrap();
+    assert_eq!(ink, read_ink(data.len() as u32));
+    assert_eq!(program.output(), data);
+    assert_eq!(program.early_exit, Some(UserOutcomeKind::Revert));
+    assert_eq!(host.traps(), 1);
+
+    unsafe { link::program_internal__set_done(UserOutcomeKind::Success) };
+    let (status, _) = program.result.unwrap();
+    assert_eq!(status, UserOutcomeKind::Revert as u32);
+}
+
+#[test]
+#[cfg(feature = "experimental")]
+fn test_revert_truncated() {
+    let host = TestHost::new();
+    Program::set_host_limits(HostLimits {
+        max_output_bytes: 8,
+        ..HostLimits::default()
+    });
+    let program = host.push_default();
+    program.append_outs(b"old");
+
+    // the revert data is subject to the output limit
+    program.revert(b"reverted data").unwrap();
+    assert_eq!(program.output(), b"reverted");
+    assert!(program.output_truncated());
+}
+
+#[test]
+fn test_program_view() {
+    use crate::program::ProgramView;
+    use std::{cell::RefCell, rc::Rc};
+
+    let host = TestHost::new();
+    host.push(&[1], EvmData::default(), StylusConfig::default());
+    let evm_data = EvmData {
+        msg_value: Bytes32::from(7_u64),
+        tracing: true,
+        ..EvmData::default()
+    };
+    let program = host.push(&[2, 3], evm_data, StylusConfig::default());
+    program.append_outs(b"out");
+
+    let traced = Rc::new(RefCell::new(vec![]));
+    let seen = traced.clone();
+    let sink = move |view: ProgramView<'_>, name: &str, _: &[u8], outs: &[u8]| {
+        let state = (view.module(), view.depth(), view.args().to_vec());
+        let output = (view.output().to_vec(), view.evm_data().msg_value);
+        seen.borrow_mut()
+            .push((name.to_owned(), state, output, outs.to_vec()));
+    };
+    program.set_trace_sink(Box::new(sink));
+    unsafe { host::user_host__msg_value(GuestPtr(0)) };
+
+    let value = Bytes32::from(7_u64);
+    let state = (program.module, 1, vec![2, 3]);
+    let expected = (
+        "msg_value".to_owned(),
+        state,
+        (b"out".to_vec(), value),
+        value.to_vec(),
+    );
+    assert_eq!(*traced.borrow(), [expected]);
+}
+
+#[test]
+#[cfg(feature = "experimental")]
+fn test_self_recursion_allowed() {
+    let _host = TestHost::new();
+    let config = StylusConfig::default();
+    Program::push_new(vec![1], EvmData::default(), 7, config);
+    Program::push_new(vec![1], EvmData::default(), 7, config);
+
+    // only an exact match is suspicious
+    Program::set_host_limits(HostLimits {
+        detect_self_recursion: true,
+        ..HostLimits::default()
+    });
+    Program::push_new(vec![2], EvmData::default(), 7, config);
+    Program::push_new(vec![2], EvmData::default(), 8, config);
+    assert_eq!(Program::depth(), 4);
+}
+
+#[test]
+#[cfg(feature = "experimental")]
+#[should_panic(expected = "program for module 7 pushed atop itself")]
+fn test_self_recursion_detected() {
+    let _host = TestHost::new();
+    Program::set_host_limits(HostLimits {
+        detect_self_recursion: true,
+        ..HostLimits::default()
+    });
+    let config = StylusConfig::default();
+    Program::push_new(vec![1], EvmData::default(), 7, config);
+    Program::push_new(vec![1], EvmData::default(), 7, config);
+}
+
+#[test]
+#[cfg(feature = "diagnostics")]
+fn test_snapshot_region() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    host.write_memory(16, &[0xaa; 32]);
+
+    let snapshot = program.snapshot_region(GuestPtr(16), 32).unwrap();
+    assert!(snapshot.diff_against(program).is_empty());
+
+    host.write_memory(20, &[9]);
+    host.write_memory(48, &[9]);
+    assert_eq!(snapshot.diff_against(program), [(4, 0xaa, 9)]);
+
+    let end = WASM_PAGE_SIZE as u32;
+    program.snapshot_region(GuestPtr(end - 16), 32).unwrap_err();
+    assert_eq!(host.traps(), 0);
+}
+
+#[test]
+fn test_set_gas_price() {
+    let host = TestHost::new();
+    let evm_data = EvmData {
+        tx_gas_price: Bytes32::from(100_u64),
+        ..EvmData::default()
+    };
+    let program = host.push(&[], evm_data, StylusConfig::default());
+    assert_eq!(program.gas_price(), Bytes32::from(100_u64));
+
+    let price = Bytes32::from(250_u64);
+    program.set_gas_price(price);
+    assert_eq!(program.gas_price(), price);
+    unsafe { host::user_host__tx_gas_price(GuestPtr(0)) };
+    assert_eq!(host.read_memory(0, 32), price.0);
+}
+
+#[test]
+fn test_selector() {
+    let host = TestHost::new();
+    let selector = [0xa9, 0x05, 0x9c, 0xbb];
+    let call = [&selector[..], &[0; 64]].concat();
+    let selector_of = |args: &[u8]| {
+        let program = host.push(args, EvmData::default(), StylusConfig::default());
+        program.selector()
+    };
+    assert_eq!(selector_of(&call), Some(selector));
+    assert_eq!(selector_of(&selector), Some(selector));
+    assert_eq!(selector_of(&selector[..3]), None);
+    assert_eq!(selector_of(&[]), None);
+}
+
+#[test]
+fn test_account_code_hash() {
+    let host = TestHost::new();
+    let code_hash = Bytes32([0xcc; 32]);
+    host.respond_with(move |_, address| {
+        let hash = match address[0] {
+            1 => code_hash,
+            2 => EMPTY_CODE_HASH,
+            _ => Bytes32::default(),
+        };
+        (hash.to_vec(), vec![], 700)
+    });
+    let program = host.push_default();
+
+    let contract = program.account_code_hash(Bytes20([1; 20])).unwrap();
+    let eoa = program.account_code_hash(Bytes20([2; 20])).unwrap();
+    let missing = program.account_code_hash(Bytes20([3; 20])).unwrap();
+    assert_eq!(contract, code_hash);
+    assert_eq!(eoa, EMPTY_CODE_HASH);
+    assert_eq!(missing, Bytes32::default());
+    assert_eq!(host.requests().len(), 3);
+}
+
+#[test]
+fn test_args_len() {
+    let host = TestHost::new();
+    let outer = host.push(
+        &[1, 2, 3, 4, 5],
+        EvmData::default(),
+        StylusConfig::default(),
+    );
+    assert_eq!(outer.args_len(), 5);
+    assert_eq!(outer.args_len(), outer.args.len());
+
+    let inner = host.push_default();
+    assert_eq!(inner.args_len(), 0);
+}
+
+#[test]
+fn test_config() {
+    let host = TestHost::new();
+    let config = StylusConfig::new(2, 5_000, 10_000);
+    let program = host.push(&[], EvmData::default(), config);
+
+    let seen = &program.config;
+    assert_eq!(seen.version, 2);
+    assert_eq!(seen.max_depth, 5_000);
+    assert_eq!(seen.pricing.ink_price, 10_000);
+    assert_eq!(seen.max_call_depth, config.max_call_depth);
+}
+
+#[test]
+fn test_output_writer() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    program.append_outs(&[0xff]);
+
+    let mut writer = program.output_writer();
+    writer
+        .write_address(Bytes20([0x11; 20]))
+        .write_u256(Bytes32::from(42_u32))
+        .write_bytes(b"hello");
+    writer.finish();
+
+    // the same tuple, encoded into a plain buffer
+    let mut expected = vec![0xff];
+    let mut writer = OutputWriter::new(&mut expected);
+    writer
+        .write_address(Bytes20([0x11; 20]))
+        .write_u256(Bytes32::from(42_u32))
+        .write_bytes(b"hello");
+    writer.finish();
+    assert_eq!(program.output(), expected);
+    assert_eq!(program.outs_high_water(), 1 + 5 * 32);
+}
+
+#[test]
+fn test_pending_call_hostios() {
+    use std::{cell::RefCell, rc::Rc};
+
+    let host = TestHost::new();
+    let seen = Rc::new(RefCell::new(vec![]));
+    let record = seen.clone();
+    host.respond_with(move |method, _| {
+        record
+            .borrow_mut()
+            .push((method, Program::current().pending_call));
+        match method {
+            EvmApiMethod::Create1 | EvmApiMethod::Create2 => {
+                let mut res = vec![1];
+                res.extend([0xaa; 20]);
+                (res, vec![], 0)
+            }
+            _ => (vec![UserOutcomeKind::Success as u8], vec![], 0),
+        }
+    });
+    let program = host.push_default();
+
+    let (zero, out) = (GuestPtr(0), GuestPtr(64));
+    unsafe {
+        host::user_host__call_contract(zero, zero, 0, zero, u64::MAX, out);
+        host::user_host__delegate_call_contract(zero, zero, 0, u64::MAX, out);
+        host::user_host__static_call_contract(zero, zero, 0, u64::MAX, out);
+        host::user_host__create1(zero, 0, zero, out, out + 32);
+        host::user_host__create2(zero, 0, zero, zero, out, out + 32);
+    }
+    let methods = [
+        EvmApiMethod::ContractCall,
+        EvmApiMethod::DelegateCall,
+        EvmApiMethod::StaticCall,
+        EvmApiMethod::Create1,
+        EvmApiMethod::Create2,
+    ];
+    let expected: Vec<_> = methods.map(|method| (method, Some(method))).into();
+    assert_eq!(*seen.borrow(), expected);
+    assert_eq!(program.pending_call, None);
+    assert_eq!(host.traps(), 0);
+}
+
+#[test]
+#[cfg(feature = "experimental")]
+fn test_pending_call_denied() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    program.set_allowed_hostios(Some(Default::default()));
+
+    let (zero, out) = (GuestPtr(0), GuestPtr(64));
+    unsafe { host::user_host__call_contract(zero, zero, 0, zero, u64::MAX, out) };
+    assert_eq!(host.traps(), 1);
+    assert!(host.requests().is_empty());
+    assert_eq!(program.pending_call, None);
+}
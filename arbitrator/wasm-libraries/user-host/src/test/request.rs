@@ -0,0 +1,348 @@
+// Copyright 2024, Offchain Labs, Inc.
+// For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
+
+#[cfg(feature = "experimental")]
+use crate::program::HostLimits;
+use crate::{host, link, program::Program, test::TestHost};
+use arbutil::{
+    evm::{
+        api::{EvmApi, EvmApiMethod, EVM_API_METHOD_REQ_OFFSET},
+        req::{RequestError, RequestStats, OUT_OF_GAS},
+        user::UserOutcomeKind,
+        EvmData,
+    },
+    Bytes20, Bytes32,
+};
+use caller_env::GuestPtr;
+use prover::programs::config::StylusConfig;
+
+#[test]
+fn test_request_ids() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    program.evm_api.get_bytes32(Bytes32([1; 32]));
+    program.evm_api.get_bytes32(Bytes32([2; 32]));
+    assert_eq!(host.request_ids(), [0x10001, 0x10002]);
+
+    // ids aren't reused, even once every program is gone
+    Program::pop();
+    let program = host.push_default();
+    program.evm_api.get_bytes32(Bytes32([3; 32]));
+    assert_eq!(host.request_ids(), [0x10001, 0x10002, 0x10003]);
+}
+
+#[test]
+fn test_unanswered_request_warning() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    let req_type = EvmApiMethod::GetBytes32 as u32 + EVM_API_METHOD_REQ_OFFSET;
+    unsafe { program.request_handler().set_request(req_type, &[0; 32]) };
+
+    let capture = Program::capture_warnings();
+    Program::pop();
+    let expected = "dropped with unanswered request GetBytes32 of 32 bytes";
+    assert_eq!(capture.into_lines(), [expected]);
+
+    // the final status of a program that's done is never answered
+    let program = host.push_default();
+    unsafe { program.request_handler().set_request(0, &[0; 8]) };
+    let capture = Program::capture_warnings();
+    Program::pop();
+    assert!(capture.into_lines().is_empty());
+}
+
+#[test]
+#[cfg(feature = "experimental")]
+fn test_oversized_response() {
+    let host = TestHost::new();
+    Program::set_host_limits(HostLimits {
+        max_response_bytes: 16,
+        ..HostLimits::default()
+    });
+    let program = host.push_default();
+    host.respond_with(|_, _| (vec![0xaa; 32], vec![], 0));
+
+    // the answer is dropped rather than kept, and the program traps
+    let (value, _) = program.evm_api.get_bytes32(Bytes32([1; 32]));
+    assert_eq!(value, Bytes32::default());
+    assert!(!program.request_handler().holds_answer());
+    assert_eq!(program.request_handler().pending_request(), None);
+    assert_eq!(host.traps(), 1);
+
+    let error = RequestError::OversizedResponse { len: 32, limit: 16 };
+    assert_eq!(program.request_handler().error(), Some(error));
+
+    // which fails the program, whatever status it finishes with
+    unsafe { link::program_internal__set_done(UserOutcomeKind::Success) };
+    let (status, gas_left) = program.result.unwrap();
+    assert_eq!(status, UserOutcomeKind::OutOfInk as u32);
+    assert_eq!(gas_left, 0);
+}
+
+#[test]
+fn test_refused_requests() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    let req_type = EvmApiMethod::GetBytes32 as u32 + EVM_API_METHOD_REQ_OFFSET;
+    let id = unsafe { program.request_handler().set_request(req_type, &[1; 32]) };
+    let failure = UserOutcomeKind::Failure as u32;
+    let (len_ptr, data_ptr) = (GuestPtr(4), GuestPtr(8));
+    let refused = [8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+    // a stale id gets the host a failure with no gas left in place of the request
+    let stale = id - 1;
+    host.write_memory(4, &[0xff; 12]);
+    let capture = Program::capture_warnings();
+    unsafe {
+        assert_eq!(link::programs__get_request(stale, len_ptr), failure);
+        link::programs__get_request_data(stale, data_ptr);
+    }
+    assert_eq!(host.read_memory(4, 12), refused);
+    let expected =
+        format!("refusing request {stale}: host answered request {stale} instead of {id}");
+    assert_eq!(capture.into_lines(), [expected]);
+
+    // while the right id still gets the request itself
+    unsafe {
+        assert_eq!(link::programs__get_request(id, len_ptr), req_type);
+        link::programs__get_request_data(id, data_ptr);
+    }
+    assert_eq!(host.read_memory(4, 4), 32_u32.to_le_bytes());
+    assert_eq!(host.read_memory(8, 32), [1; 32]);
+
+    // whereas a request of unknown type is refused too
+    let unknown = req_type + 0xffff;
+    let id = unsafe { program.request_handler().set_request(unknown, &[1; 32]) };
+    host.write_memory(4, &[0xff; 12]);
+    let capture = Program::capture_warnings();
+    unsafe {
+        assert_eq!(link::programs__get_request(id, len_ptr), failure);
+        link::programs__get_request_data(id, data_ptr);
+    }
+    assert_eq!(host.read_memory(4, 12), refused);
+    let expected = format!("refusing request {id} of unknown type {unknown:#x}");
+    assert_eq!(capture.into_lines(), [expected]);
+}
+
+#[test]
+fn test_out_of_gas() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    host.respond_with(|_, _| (vec![0xaa; 32], vec![], OUT_OF_GAS));
+
+    // the sentinel fails the request
+    let (value, _) = program.evm_api.get_bytes32(Bytes32([1; 32]));
+    assert_eq!(value, Bytes32::default());
+    let error = program.request_handler().error();
+    assert_eq!(error, Some(RequestError::OutOfGas));
+    assert_eq!(host.traps(), 1);
+
+    unsafe { link::program_internal__set_done(UserOutcomeKind::Success) };
+    let (status, _) = program.result.unwrap();
+    assert_eq!(status, UserOutcomeKind::OutOfInk as u32);
+}
+
+#[test]
+fn test_request_stats() {
+    let host = TestHost::new();
+    let program = host.push_default();
+    program.evm_api.get_bytes32(Bytes32([1; 32]));
+    program.evm_api.get_bytes32(Bytes32([2; 32]));
+    program.evm_api.account_balance(Bytes20([3; 20]));
+
+    let stats = program.request_handler().take_request_stats();
+    assert_eq!(stats.get(EvmApiMethod::GetBytes32), 2);
+    assert_eq!(stats.get(EvmApiMethod::AccountBalance), 1);
+    assert_eq!(stats.total(), 3);
+
+    // taking the stats zeroes them
+    let stats = program.request_handler().take_request_stats();
+    assert_eq!(stats, RequestStats::default());
+}
+
+#[test]
+#[cfg(feature = "experimental")]
+fn test_max_requests() {
+    let host = TestHost::new();
+    Program::set_host_limits(HostLimits {
+        max_requests: 3,
+        ..HostLimits::default()
+    });
+    let program = host.push_default();
+    for i in 1..=3 {
+        program.evm_api.get_bytes32(Bytes32([i; 32]));
+    }
+    assert_eq!(host.traps(), 0);
+
+    // taking the stats doesn't affect the limit, which counts every request made
+    program.request_handler().take_request_stats();
+    program.evm_api.get_bytes32(Bytes32([4; 32]));
+    let error = program.request_handler().error();
+    assert_eq!(error, Some(RequestError::RequestLimitExceeded { limit: 3 }));
+    assert_eq!(host.requests().len(), 3);
+}
+
+#[test]
+#[cfg(feature = "experimental")]
+fn test_oversized_payload() {
+    let host = TestHost::new();
+    Program::set_host_limits(HostLimits {
+        max_request_bytes: 32,
+        ..HostLimits::default()
+    });
+    let program = host.push_default();
+
+    // a storage key fits, but a log with data past the limit doesn't
+    program.evm_api.get_bytes32(Bytes32([1; 32]));
+    assert_eq!(host.traps(), 0);
+
+    let _ = program.evm_api.emit_log(vec![0; 40], 1);
+    let error = RequestError::OversizedPayload { len: 44, limit: 32 };
+    assert_eq!(program.request_handler().error(), Some(error));
+    assert_eq!(host.requests().len(), 1);
+    assert_eq!(host.traps(), 1);
+}
+
+#[test]
+#[cfg(feature = "experimental")]
+fn test_gas_deadline() {
+    let host = TestHost::new();
+    Program::set_host_limits(HostLimits {
+        gas_deadline: 250,
+        ..HostLimits::default()
+    });
+    let program = host.push_default();
+    host.respond_with(|_, _| (vec![0; 32], vec![], 200));
+
+    // the deadline is checked before each request, so the one crossing it still goes through
+    program.evm_api.get_bytes32(Bytes32([1; 32]));
+    program.evm_api.get_bytes32(Bytes32([2; 32]));
+    assert_eq!(host.traps(), 0);
+
+    program.evm_api.get_bytes32(Bytes32([3; 32]));
+    let error = RequestError::DeadlineExceeded {
+        gas_used: 400,
+        deadline: 250,
+    };
+    assert_eq!(program.request_handler().error(), Some(error));
+    assert_eq!(host.requests().len(), 2);
+    assert_eq!(host.traps(), 1);
+}
+
+#[test]
+fn test_min_gas_reserve() {
+    let host = TestHost::new();
+    let config = StylusConfig {
+        min_gas_reserve: 900,
+        ..StylusConfig::default()
+    };
+    let program = host.push(&[], EvmData::default(), config);
+    host.respond_with(|_, _| (vec![0xaa; 32], vec![], 200));
+    host.set_ink(1_000);
+
+    let (value, _) = program.evm_api.get_bytes32(Bytes32([1; 32]));
+    assert_eq!(value, Bytes32([0xaa; 32]));
+    assert_eq!(host.traps(), 0);
+
+    // the next request is refused, as the gas left on the meter is now below the reserve
+    host.set_ink(800);
+    let (value, _) = program.evm_api.get_bytes32(Bytes32([2; 32]));
+    assert_eq!(value, Bytes32::default());
+    let error = RequestError::BelowGasReserve {
+        gas_left: 800,
+        reserve: 900,
+    };
+    assert_eq!(program.request_handler().error(), Some(error));
+    assert_eq!(host.requests().len(), 1);
+    assert_eq!(host.traps(), 1);
+}
+
+#[test]
+fn test_min_gas_reserve_hostio() {
+    let host = TestHost::new();
+    let config = StylusConfig {
+        min_gas_reserve: 50_000,
+        ..StylusConfig::default()
+    };
+    host.push(&[], EvmData::default(), config);
+
+    // the reserve applies to the gas left once the hostio's own costs are paid
+    host.set_ink(200_000);
+    unsafe { host::user_host__transient_load_bytes32(GuestPtr(0), GuestPtr(32)) };
+    assert_eq!(host.requests().len(), 1);
+    assert_eq!(host.traps(), 0);
+
+    host.set_ink(100_000);
+    unsafe { host::user_host__transient_load_bytes32(GuestPtr(0), GuestPtr(32)) };
+    assert_eq!(host.requests().len(), 1);
+    assert_eq!(host.traps(), 1);
+}
+
+#[test]
+fn test_pending_requests() {
+    let host = TestHost::new();
+    let req_type = |method: EvmApiMethod| method as u32 + EVM_API_METHOD_REQ_OFFSET;
+    let get_bytes32 = req_type(EvmApiMethod::GetBytes32);
+    let balance = req_type(EvmApiMethod::AccountBalance);
+
+    let outer = host.push_default();
+    let id = unsafe { outer.request_handler().set_request(get_bytes32, &[1; 32]) };
+    host.push_default();
+    let inner = host.push_default();
+    unsafe { inner.request_handler().set_request(balance, &[2; 20]) };
+
+    let expected = [(0, get_bytes32, vec![1; 32]), (2, balance, vec![2; 20])];
+    assert_eq!(Program::pending_requests(), expected);
+
+    // an answered request is no longer pending
+    let handler = outer.request_handler();
+    unsafe { handler.set_response(id, vec![0; 32], vec![], 0).unwrap() };
+    assert_eq!(Program::pending_requests(), expected[1..]);
+}
+
+#[test]
+fn test_request_interceptor() {
+    use std::sync::{Arc, Mutex};
+
+    let host = TestHost::new();
+    let program = host.push_default();
+    let seen = Arc::new(Mutex::new(vec![]));
+    let record = seen.clone();
+    let interceptor = move |method: EvmApiMethod, data: &[u8]| {
+        record.lock().unwrap().push((method, data.to_vec()));
+    };
+    program
+        .request_handler()
+        .set_request_interceptor(Box::new(interceptor));
+
+    let (key, address) = (Bytes32([1; 32]), Bytes20([2; 20]));
+    program.evm_api.get_bytes32(key);
+    program.evm_api.account_balance(address);
+    program.evm_api.get_bytes32(key);
+
+    let expected = [
+        (EvmApiMethod::GetBytes32, key.to_vec()),
+        (EvmApiMethod::AccountBalance, address.to_vec()),
+    ];
+    assert_eq!(*seen.lock().unwrap(), expected);
+    assert_eq!(host.requests(), expected);
+}
+
+#[test]
+fn test_gas_by_frame() {
+    let host = TestHost::new();
+    host.respond_with(|_, data| (vec![0; 32], vec![], 100 * data[0] as u64));
+
+    let outer = host.push_default();
+    outer.evm_api.get_bytes32(Bytes32([3; 32]));
+    let inner = host.push_default();
+    inner.evm_api.get_bytes32(Bytes32([2; 32]));
+    inner.evm_api.get_bytes32(Bytes32([5; 32]));
+
+    let frames = [(outer.module, 300), (inner.module, 700)];
+    assert_eq!(Program::gas_by_frame(), frames);
+
+    // frames that have returned are no longer attributed anything
+    Program::pop();
+    assert_eq!(Program::gas_by_frame(), frames[..1]);
+}
@@ -217,6 +217,21 @@ pub unsafe extern "C" fn vm_hooks__native_keccak256(input: GuestPtr, len: u32, o
     hostio!(native_keccak256(input, len, output))
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn vm_hooks__selfdestruct(beneficiary: GuestPtr) {
+    hostio!(selfdestruct(beneficiary))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn vm_hooks__flush_output() {
+    hostio!(flush_output())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn vm_hooks__mcopy(dest: GuestPtr, src: GuestPtr, len: u32) {
+    hostio!(mcopy(dest, src, len))
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn vm_hooks__tx_gas_price(ptr: GuestPtr) {
     hostio!(tx_gas_price(ptr))
@@ -1,30 +1,21 @@
 // Copyright 2022-2024, Offchain Labs, Inc.
 // For license information, see https://github.com/OffchainLabs/nitro/blob/master/LICENSE
 
-use crate::{ARGS, EVER_PAGES, EVM_DATA, KEYS, LOGS, OPEN_PAGES, OUTS};
+use crate::{ARGS, CONFIG, EVER_PAGES, EVM_DATA, KEYS, LOGS, OPEN_PAGES, OUTS};
 use arbutil::{
     evm::{
         api::{EvmApi, VecReader},
         user::UserOutcomeKind,
         EvmData,
     },
-    Bytes20, Bytes32, Color,
+    Bytes20, Bytes32, Color, MemoryBoundsError,
 };
 use caller_env::{static_caller::STATIC_MEM, GuestPtr, MemAccess};
-use eyre::{eyre, Result};
+use eyre::Result;
 use prover::programs::memory::MemoryModel;
 use std::fmt::Display;
 use user_host_trait::UserHost;
 
-/// Signifies an out-of-bounds memory access was requested.
-pub struct MemoryBoundsError;
-
-impl From<MemoryBoundsError> for eyre::ErrReport {
-    fn from(_: MemoryBoundsError) -> Self {
-        eyre!("memory access out of bounds")
-    }
-}
-
 /// Mock type representing a `user_host::Program`
 pub struct Program {
     evm_api: MockEvmApi,
@@ -52,6 +43,10 @@ impl UserHost<VecReader> for Program {
         &EVM_DATA
     }
 
+    fn min_gas_reserve(&self) -> u64 {
+        unsafe { CONFIG.unwrap().min_gas_reserve }
+    }
+
     fn evm_return_data_len(&mut self) -> &mut u32 {
         unimplemented!()
     }
@@ -189,6 +184,14 @@ impl EvmApi<VecReader> for MockEvmApi {
         unimplemented!()
     }
 
+    fn self_destruct(&mut self, _beneficiary: Bytes20) -> Result<u64> {
+        unimplemented!()
+    }
+
+    fn flush_output(&mut self, _data: &[u8]) -> Result<()> {
+        unimplemented!()
+    }
+
     fn account_code(&mut self, _address: Bytes20, _gas_left: u64) -> (VecReader, u64) {
         unimplemented!()
     }
@@ -197,6 +200,18 @@ impl EvmApi<VecReader> for MockEvmApi {
         unimplemented!()
     }
 
+    fn account_code_size(&mut self, _address: Bytes20) -> (u32, u64) {
+        unimplemented!()
+    }
+
+    fn block_hash(&mut self, _number: u64) -> (Bytes32, u64) {
+        unimplemented!()
+    }
+
+    fn prefetch_bytes32(&mut self, _keys: &[Bytes32]) -> u64 {
+        unimplemented!()
+    }
+
     fn add_pages(&mut self, pages: u16) -> u64 {
         let model = MemoryModel::new(2, 1000);
         unsafe {
@@ -36,6 +36,11 @@ const (
 	AccountCodeHash
 	AddPages
 	CaptureHostIO
+	AccountCodeSize
+	BlockHash
+	PrefetchBytes32
+	SelfDestruct
+	FlushOutput
 )
 
 type apiStatus uint8
@@ -58,6 +63,7 @@ func newApiClosures(
 	tracingInfo *util.TracingInfo,
 	scope *vm.ScopeContext,
 	memoryModel *MemoryModel,
+	flushed *[]byte,
 ) RequestHandler {
 	contract := scope.Contract
 	actingAddress := contract.Address() // not necessarily WASM
@@ -235,6 +241,20 @@ func newApiClosures(
 		balance := evm.StateDB.GetBalance(address)
 		return balance.Bytes32(), cost
 	}
+	selfDestruct := func(beneficiary common.Address) (apiStatus, uint64) {
+		if readOnly {
+			return WriteProtection, 0
+		}
+		cost := vm.WasmAccountTouchCost(chainConfig, db, beneficiary, false)
+		balance := db.GetBalance(actingAddress)
+		if !balance.IsZero() && db.Empty(beneficiary) {
+			cost = am.SaturatingUAdd(cost, params.CreateBySelfdestructGas)
+		}
+		db.SubBalance(actingAddress, balance)
+		db.AddBalance(beneficiary, balance)
+		db.Selfdestruct6780(actingAddress)
+		return Success, cost
+	}
 	accountCode := func(address common.Address, gas uint64) ([]byte, uint64) {
 		// In the future it'll be possible to know the size of a contract before loading it.
 		// For now, require the worst case before doing the load.
@@ -249,6 +269,17 @@ func newApiClosures(
 		cost := vm.WasmAccountTouchCost(chainConfig, evm.StateDB, address, false)
 		return evm.StateDB.GetCodeHash(address), cost
 	}
+	accountCodeSize := func(address common.Address) (uint32, uint64) {
+		cost := vm.WasmAccountTouchCost(chainConfig, evm.StateDB, address, false)
+		return uint32(evm.StateDB.GetCodeSize(address)), cost
+	}
+	blockHash := func(number uint64) common.Hash {
+		hash, err := evm.ProcessingHook.L1BlockHash(evm.Context, number)
+		if err != nil {
+			return common.Hash{}
+		}
+		return hash
+	}
 	addPages := func(pages uint16) uint64 {
 		open, ever := db.AddStylusPages(pages)
 		return memoryModel.GasCost(pages, open, ever)
@@ -380,6 +411,10 @@ func newApiClosures(
 			address := takeAddress()
 			balance, cost := accountBalance(address)
 			return balance[:], nil, cost
+		case SelfDestruct:
+			beneficiary := takeAddress()
+			status, cost := selfDestruct(beneficiary)
+			return status.to_slice(), nil, cost
 		case AccountCode:
 			address := takeAddress()
 			gas := takeU64()
@@ -409,6 +444,27 @@ func newApiClosures(
 
 			captureHostio(name, args, outs, startInk, endInk)
 			return []byte{}, nil, 0
+		case AccountCodeSize:
+			address := takeAddress()
+			size, cost := accountCodeSize(address)
+			return arbmath.Uint32ToBytes(size), nil, cost
+		case BlockHash:
+			number := takeU64()
+			hash := blockHash(number)
+			return hash[:], nil, 0
+		case PrefetchBytes32:
+			values := make([]byte, 0, len(input))
+			total := uint64(0)
+			for len(input) > 0 {
+				out, cost := getBytes32(takeHash())
+				values = append(values, out[:]...)
+				total = arbmath.SaturatingUAdd(total, cost)
+			}
+			return values, nil, total
+		case FlushOutput:
+			// the output is prepended to whatever the program returns once done
+			*flushed = append(*flushed, takeRest()...)
+			return Success.to_slice(), nil, 0
 		default:
 			log.Crit("unsupported call type", "req", req)
 			return []byte{}, nil, 0
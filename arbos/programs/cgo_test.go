@@ -20,6 +20,13 @@ func TestConstants(t *testing.T) {
 	}
 }
 
+func TestStylusConfig(t *testing.T) {
+	err := testStylusConfig()
+	if err != nil {
+		t.Fatal(err)
+	}
+}
+
 // normal test will not write anything to disk
 // to test cross-compilation:
 // * run test with TEST_COMPILE=STORE on one machine
@@ -29,6 +29,7 @@ import (
 	"github.com/ethereum/go-ethereum/core/vm"
 	"github.com/ethereum/go-ethereum/ethdb"
 	"github.com/ethereum/go-ethereum/log"
+	gethParams "github.com/ethereum/go-ethereum/params"
 	"github.com/offchainlabs/nitro/arbos/burn"
 	"github.com/offchainlabs/nitro/arbos/util"
 	"github.com/offchainlabs/nitro/arbutil"
@@ -232,7 +233,8 @@ func callProgram(
 		stateDb.RecordProgram(db.Database().WasmTargets(), moduleHash)
 	}
 
-	evmApi := newApi(interpreter, tracingInfo, scope, memoryModel)
+	var flushed []byte // output the program streamed out before finishing
+	evmApi := newApi(interpreter, tracingInfo, scope, memoryModel, &flushed)
 	defer evmApi.drop()
 
 	output := &rustBytes{}
@@ -249,7 +251,7 @@ func callProgram(
 	))
 
 	depth := interpreter.Depth()
-	data, msg, err := status.toResult(output.intoBytes(), debug)
+	data, msg, err := status.toResult(append(flushed, output.intoBytes()...), debug)
 	if status == userFailure && debug {
 		log.Warn("program failure", "err", err, "msg", msg, "program", address, "depth", depth)
 	}
@@ -383,9 +385,10 @@ func (params *ProgParams) encode() C.StylusConfig {
 		ink_price: u32(params.InkPrice.ToUint32()),
 	}
 	return C.StylusConfig{
-		version:   u16(params.Version),
-		max_depth: u32(params.MaxDepth),
-		pricing:   pricing,
+		version:        u16(params.Version),
+		max_depth:      u32(params.MaxDepth),
+		pricing:        pricing,
+		max_call_depth: u32(gethParams.CallCreateDepth),
 	}
 }
 
@@ -397,6 +400,7 @@ func (data *EvmData) encode() C.EvmData {
 		block_gas_limit:  u64(data.blockGasLimit),
 		block_number:     u64(data.blockNumber),
 		block_timestamp:  u64(data.blockTimestamp),
+		block_randomness: hashToBytes32(data.blockRandomness),
 		contract_address: addressToBytes20(data.contractAddress),
 		module_hash:      hashToBytes32(data.moduleHash),
 		msg_sender:       addressToBytes20(data.msgSender),
@@ -407,5 +411,6 @@ func (data *EvmData) encode() C.EvmData {
 		return_data_len:  0,
 		cached:           cbool(data.cached),
 		tracing:          cbool(data.tracing),
+		read_only:        cbool(data.readOnly),
 	}
 }
@@ -47,8 +47,9 @@ func newApi(
 	tracingInfo *util.TracingInfo,
 	scope *vm.ScopeContext,
 	memoryModel *MemoryModel,
+	flushed *[]byte,
 ) NativeApi {
-	handler := newApiClosures(interpreter, tracingInfo, scope, memoryModel)
+	handler := newApiClosures(interpreter, tracingInfo, scope, memoryModel, flushed)
 	apiId := apiIds.Add(1)
 	id := usize(apiId)
 	api := NativeApi{
@@ -221,6 +221,11 @@ func (p Programs) CallProgram(
 		return nil, err
 	}
 
+	var blockRandomness common.Hash
+	if evm.Context.Random != nil {
+		blockRandomness = *evm.Context.Random
+	}
+
 	evmData := &EvmData{
 		blockBasefee:    common.BigToHash(evm.Context.BaseFee),
 		chainId:         evm.ChainConfig().ChainID.Uint64(),
@@ -228,6 +233,7 @@ func (p Programs) CallProgram(
 		blockGasLimit:   evm.Context.GasLimit,
 		blockNumber:     l1BlockNumber,
 		blockTimestamp:  evm.Context.Time,
+		blockRandomness: blockRandomness,
 		contractAddress: scope.Contract.Address(),
 		moduleHash:      moduleHash,
 		msgSender:       scope.Contract.Caller(),
@@ -237,6 +243,7 @@ func (p Programs) CallProgram(
 		reentrant:       am.BoolToUint32(reentrant),
 		cached:          program.cached,
 		tracing:         tracingInfo != nil,
+		readOnly:        interpreter.ReadOnly(),
 	}
 
 	address := contract.Address()
@@ -523,6 +530,7 @@ type EvmData struct {
 	blockGasLimit   uint64
 	blockNumber     uint64
 	blockTimestamp  uint64
+	blockRandomness common.Hash
 	contractAddress common.Address
 	moduleHash      common.Hash
 	msgSender       common.Address
@@ -532,6 +540,7 @@ type EvmData struct {
 	reentrant       uint32
 	cached          bool
 	tracing         bool
+	readOnly        bool
 }
 
 type activationInfo struct {
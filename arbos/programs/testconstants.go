@@ -13,7 +13,11 @@ package programs
 #include "arbitrator.h"
 */
 import "C"
-import "fmt"
+import (
+	"fmt"
+
+	gethParams "github.com/ethereum/go-ethereum/params"
+)
 
 func testConstants() error {
 
@@ -72,6 +76,21 @@ func testConstants() error {
 	if err := errIfNotEq(CaptureHostIO, C.EvmApiMethod_CaptureHostIO); err != nil {
 		return err
 	}
+	if err := errIfNotEq(AccountCodeSize, C.EvmApiMethod_AccountCodeSize); err != nil {
+		return err
+	}
+	if err := errIfNotEq(BlockHash, C.EvmApiMethod_BlockHash); err != nil {
+		return err
+	}
+	if err := errIfNotEq(PrefetchBytes32, C.EvmApiMethod_PrefetchBytes32); err != nil {
+		return err
+	}
+	if err := errIfNotEq(SelfDestruct, C.EvmApiMethod_SelfDestruct); err != nil {
+		return err
+	}
+	if err := errIfNotEq(FlushOutput, C.EvmApiMethod_FlushOutput); err != nil {
+		return err
+	}
 	if err := errIfNotEq(EvmApiMethodReqOffset, C.EVM_API_METHOD_REQ_OFFSET); err != nil {
 		return err
 	}
@@ -99,3 +118,14 @@ func testConstants() error {
 	}
 	return nil
 }
+
+// The replay machine builds its config in Rust, which defaults to Geth's call depth limit,
+// so the native config must carry the same limit for programs to see the same depth remaining.
+func testStylusConfig() error {
+	params := &ProgParams{Version: 2, MaxDepth: 5, InkPrice: 10000}
+	config := params.encode()
+	if uint64(config.max_call_depth) != gethParams.CallCreateDepth {
+		return fmt.Errorf("max call depth %d != %d", config.max_call_depth, gethParams.CallCreateDepth)
+	}
+	return nil
+}
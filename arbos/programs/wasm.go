@@ -145,8 +145,9 @@ func callProgram(
 	memoryModel *MemoryModel,
 	_arbos_tag uint32,
 ) ([]byte, error) {
-	reqHandler := newApiClosures(interpreter, tracingInfo, scope, memoryModel)
-	gasLeft, retData, err := CallProgramLoop(moduleHash, calldata, scope.Contract.Gas, evmData, params, reqHandler)
+	var flushed []byte // output the program streamed out before finishing
+	reqHandler := newApiClosures(interpreter, tracingInfo, scope, memoryModel, &flushed)
+	gasLeft, retData, err := CallProgramLoop(moduleHash, calldata, scope.Contract.Gas, evmData, params, reqHandler, &flushed)
 	scope.Contract.Gas = gasLeft
 	return retData, err
 }
@@ -157,7 +158,8 @@ func CallProgramLoop(
 	gas uint64,
 	evmData *EvmData,
 	params *ProgParams,
-	reqHandler RequestHandler) (uint64, []byte, error) {
+	reqHandler RequestHandler,
+	flushed *[]byte) (uint64, []byte, error) {
 	configHandler := params.createHandler()
 	dataHandler := evmData.createHandler()
 	debug := params.DebugMode
@@ -180,7 +182,7 @@ func CallProgramLoop(
 			popProgram()
 			status := userStatus(reqTypeId)
 			gasLeft := arbmath.BytesToUint(reqData[:8])
-			data, msg, err := status.toResult(reqData[8:], debug)
+			data, msg, err := status.toResult(append(*flushed, reqData[8:]...), debug)
 			if status == userFailure && debug {
 				log.Warn("program failure", "err", err, "msg", msg, "moduleHash", moduleHash)
 			}
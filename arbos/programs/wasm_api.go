@@ -28,6 +28,7 @@ func createEvmData(
 	gasLimit uint64,
 	blockNumber uint64,
 	blockTimestamp uint64,
+	blockRandomness unsafe.Pointer,
 	contractAddress unsafe.Pointer,
 	moduleHash unsafe.Pointer,
 	msgSender unsafe.Pointer,
@@ -36,6 +37,7 @@ func createEvmData(
 	txOrigin unsafe.Pointer,
 	cached uint32,
 	reentrant uint32,
+	readOnly uint32,
 ) evmDataHandler
 
 func (params *ProgParams) createHandler() stylusConfigHandler {
@@ -51,6 +53,7 @@ func (data *EvmData) createHandler() evmDataHandler {
 		data.blockGasLimit,
 		data.blockNumber,
 		data.blockTimestamp,
+		arbutil.SliceToUnsafePointer(data.blockRandomness[:]),
 		arbutil.SliceToUnsafePointer(data.contractAddress[:]),
 		arbutil.SliceToUnsafePointer(data.moduleHash[:]),
 		arbutil.SliceToUnsafePointer(data.msgSender[:]),
@@ -59,5 +62,6 @@ func (data *EvmData) createHandler() evmDataHandler {
 		arbutil.SliceToUnsafePointer(data.txOrigin[:]),
 		arbmath.BoolToUint32(data.cached),
 		data.reentrant,
+		arbmath.BoolToUint32(data.readOnly),
 	)
 }
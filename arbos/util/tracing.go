@@ -451,6 +451,22 @@ func (info *TracingInfo) CaptureEVMTraceForHostio(name string, args, outs []byte
 		capture(vm.KECCAK256, args, offset, size)
 		capture(vm.POP, args, hash)
 
+	case "mcopy":
+		if !checkArgs(4 + 4 + 4) {
+			return
+		}
+		destOffset := args[:4]
+		offset := args[4:8]
+		size := args[8:12]
+		capture(vm.MCOPY, nil, destOffset, offset, size)
+
+	case "selfdestruct":
+		if !checkArgs(20) {
+			return
+		}
+		beneficiary := args[:20]
+		capture(vm.SELFDESTRUCT, nil, beneficiary)
+
 	case "tx_gas_price":
 		if !checkOuts(32) {
 			return
@@ -482,7 +498,7 @@ func (info *TracingInfo) CaptureEVMTraceForHostio(name string, args, outs []byte
 	case "write_result", "exit_early":
 		// These calls are handled on CaptureStylusExit to also cover the normal exit case.
 
-	case "user_entrypoint", "user_returned", "msg_reentrant", "pay_for_memory_grow", "console_log_text", "console_log":
+	case "user_entrypoint", "user_returned", "msg_reentrant", "pay_for_memory_grow", "console_log_text", "console_log", "flush_output":
 		// No EVM counterpart
 
 	default: